@@ -11,7 +11,7 @@ pub const HEIGHT: f32 = NUM_POINTS_Y as f32 * DY;
 
 pub const DT: f32 = 5.39e-11;
 
-pub const MASS: f32 = 9.1093837e-31;
+pub const MASS: f32 = 9.109_383e-31;
 
 pub const Y_SCALE: f32 = 0.25;
 pub const XZ_SCALE: f32 = 1.0 / (DX * NUM_POINTS_X as f32);
@@ -24,4 +24,31 @@ pub const COMMON_SIMULATION_SETTINGS: wave::SimulationSettings =
 pub const COMMON_RENDER_SETTINGS: wave::RenderSettings =
     wave::RenderSettings::new(NUM_POINTS_X, NUM_POINTS_Y, Y_SCALE, XZ_SCALE);
 
+// The same grid in atomic units (bohr radii, h_bar / hartree and electron masses), for the examples
+// whose packets and potentials are given in them
+pub mod atomic {
+    pub use super::{NUM_POINTS_X, NUM_POINTS_Y, SUB_STEPS, Y_SCALE};
+
+    pub const DX: f32 = 1.0;
+    pub const DY: f32 = DX;
+
+    pub const WIDTH: f32 = NUM_POINTS_X as f32 * DX;
+    pub const HEIGHT: f32 = NUM_POINTS_Y as f32 * DY;
+
+    pub const DT: f32 = 0.05;
+
+    pub const MASS: f32 = 1.0;
+
+    pub const XZ_SCALE: f32 = 1.0 / (DX * NUM_POINTS_X as f32);
+
+    pub const TIME_SCALE: f32 = 1.0 / (60.0 * DT * SUB_STEPS as f32);
+
+    pub const COMMON_SIMULATION_SETTINGS: wave::SimulationSettings =
+        wave::SimulationSettings::new(NUM_POINTS_X, NUM_POINTS_Y, DX, DY, DT, MASS)
+            .with_units(wave::UnitSystem::Atomic)
+            .with_integrator(wave::Integrator::Leapfrog);
+    pub const COMMON_RENDER_SETTINGS: wave::RenderSettings =
+        wave::RenderSettings::new(NUM_POINTS_X, NUM_POINTS_Y, Y_SCALE, XZ_SCALE);
+}
+
 fn main() {}
@@ -0,0 +1,64 @@
+use common::atomic::*;
+use std::f32::consts::PI;
+use wave::potentials::{Potential, SlitWall};
+use wave::states::{GaussianPacket, InitialState};
+
+mod common;
+
+struct DoubleSlitSimulation {
+    packet: GaussianPacket,
+    wall: SlitWall,
+}
+
+// A packet passing through two slits in a hard wall, interfering on the far side
+const SIGMA: f32 = 16.0;
+const K: f32 = 1.0;
+const SLIT_WIDTH: f32 = 6.0;
+const SLIT_SEPARATION: f32 = 20.0;
+const WALL_THICKNESS: f32 = 2.0;
+
+fn main() {
+    wave::run::<DoubleSlitSimulation>();
+}
+
+impl wave::Simulation for DoubleSlitSimulation {
+    fn new() -> Self {
+        DoubleSlitSimulation {
+            packet: GaussianPacket::new(-WIDTH / 4.0, 0.0, SIGMA, K, 0.0),
+            wall: SlitWall::double(
+                -WIDTH / 8.0,
+                WALL_THICKNESS,
+                f32::INFINITY,
+                SLIT_WIDTH,
+                SLIT_SEPARATION,
+            ),
+        }
+    }
+
+    fn simulation_settings(&self) -> wave::SimulationSettings {
+        COMMON_SIMULATION_SETTINGS
+            .with_normalization(true)
+            .with_absorbing_layer(wave::AbsorbingLayer::new(24, 1.0))
+    }
+
+    fn render_settings(&self) -> wave::RenderSettings {
+        wave::RenderSettings::new(
+            NUM_POINTS_X,
+            NUM_POINTS_Y,
+            Y_SCALE * 2.0 * PI * SIGMA * SIGMA,
+            XZ_SCALE,
+        )
+    }
+
+    fn time_scale(&self) -> f32 {
+        TIME_SCALE
+    }
+
+    fn psi_0(&self, x: f32, y: f32) -> wave::Complex {
+        self.packet.psi(x, y)
+    }
+
+    fn potential(&self, x: f32, y: f32) -> f32 {
+        self.wall.v(x, y)
+    }
+}
@@ -25,7 +25,7 @@ impl wave::Simulation for EmptySimulation {
         TIME_SCALE
     }
 
-    fn psi_0(&self, _: f32, _: f32) -> (f32, f32) {
-        (0.0, 0.0)
+    fn psi_0(&self, _: f32, _: f32) -> wave::Complex {
+        wave::Complex::ZERO
     }
 }
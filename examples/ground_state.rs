@@ -0,0 +1,71 @@
+use common::atomic::*;
+use std::f32::consts::PI;
+use wave::potentials::{HarmonicTrap, Potential};
+use wave::states::{GaussianPacket, InitialState};
+
+mod common;
+
+// Finds the ground state of a trap in imaginary time, then releases it displaced from the centre of
+// the trap so it oscillates back and forth without changing shape
+struct GroundStateSimulation {
+    trap: HarmonicTrap,
+    ground_state: Option<wave::Eigenstate<f64>>,
+}
+
+const OMEGA: f32 = 0.01;
+const OFFSET: f32 = WIDTH / 8.0;
+
+const TOLERANCE: f64 = 1e-6;
+const MAX_STEPS: usize = 100_000;
+
+fn main() {
+    wave::run::<GroundStateSimulation>();
+}
+
+impl wave::Simulation for GroundStateSimulation {
+    fn new() -> Self {
+        let mut simulation = GroundStateSimulation {
+            trap: HarmonicTrap::new(MASS, OMEGA),
+            ground_state: None,
+        };
+
+        // Relaxed in double precision, where the far tails of the state don't sink into the slow
+        // subnormal range of f32
+        let mut imaginary_time = wave::ImaginaryTime::<f64>::with_precision(&simulation).unwrap();
+        let ground_state = imaginary_time.find(TOLERANCE, MAX_STEPS).unwrap();
+        println!("ground state energy = {:e}", ground_state.energy());
+
+        simulation.ground_state = Some(ground_state.clone());
+        simulation
+    }
+
+    fn simulation_settings(&self) -> wave::SimulationSettings {
+        COMMON_SIMULATION_SETTINGS
+    }
+
+    fn render_settings(&self) -> wave::RenderSettings {
+        // Scales the peak density of the ground state, m * omega / (pi * h_bar), up to a visible height
+        wave::RenderSettings::new(
+            NUM_POINTS_X,
+            NUM_POINTS_Y,
+            Y_SCALE * PI / (MASS * OMEGA),
+            XZ_SCALE,
+        )
+    }
+
+    fn time_scale(&self) -> f32 {
+        TIME_SCALE
+    }
+
+    // A Gaussian guess to relax from until the ground state is known
+    fn psi_0(&self, x: f32, y: f32) -> wave::Complex {
+        match &self.ground_state {
+            Some(ground_state) => ground_state.psi(x - OFFSET, y),
+            None => GaussianPacket::new(0.0, 0.0, WIDTH / 16.0, 0.0, 0.0).psi(x, y),
+        }
+    }
+
+    fn potential(&self, x: f32, y: f32) -> f32 {
+        self.trap.v(x, y)
+    }
+}
@@ -0,0 +1,69 @@
+use common::atomic::{COMMON_RENDER_SETTINGS, TIME_SCALE};
+use std::f32::consts::PI;
+
+mod common;
+
+struct HeadlessSimulation;
+
+const NUM_POINTS: usize = 64;
+const DX: f32 = 1.0;
+const WIDTH: f32 = NUM_POINTS as f32 * DX;
+const DT: f32 = 1e-3;
+const MASS: f32 = 1.0;
+
+const STEPS: usize = 100;
+const NORM_TOLERANCE: f32 = 1e-3;
+const RECORDING_PATH: &str = "headless.csv";
+const SNAPSHOT_DIRECTORY: &str = "headless_snapshots";
+
+impl wave::Simulation for HeadlessSimulation {
+    fn new() -> Self {
+        HeadlessSimulation
+    }
+
+    fn simulation_settings(&self) -> wave::SimulationSettings {
+        wave::SimulationSettings::new(NUM_POINTS, NUM_POINTS, DX, DX, DT, MASS)
+            .with_units(wave::UnitSystem::Dimensionless)
+            .with_integrator(wave::Integrator::Leapfrog)
+            .with_normalization(true)
+            .with_diagnostics(
+                wave::Diagnostics::new(STEPS)
+                    .with_norm_tolerance(NORM_TOLERANCE)
+                    .with_action(wave::DriftAction::Abort),
+            )
+            .with_recording(wave::Recording::csv(RECORDING_PATH, STEPS))
+            .with_snapshots(wave::Snapshots::new(SNAPSHOT_DIRECTORY, 5 * STEPS))
+    }
+
+    fn render_settings(&self) -> wave::RenderSettings {
+        COMMON_RENDER_SETTINGS
+    }
+
+    fn time_scale(&self) -> f32 {
+        TIME_SCALE
+    }
+
+    fn psi_0(&self, x: f32, y: f32) -> wave::Complex {
+        wave::Complex::from((2.0 * PI * x / WIDTH).sin() * (2.0 * PI * y / WIDTH).sin())
+    }
+}
+
+fn main() -> Result<(), wave::Error> {
+    let simulation = <HeadlessSimulation as wave::Simulation>::new();
+    let mut solver = wave::Solver::new(&simulation)?;
+    println!("psi_0 norm = {:e}", solver.initial_norm());
+
+    for _ in 0..10 {
+        solver.step(STEPS)?;
+
+        let measurement = solver.measure();
+        println!(
+            "t = {:e}, norm = {:e}, energy = {:e}",
+            measurement.time(),
+            measurement.norm(),
+            measurement.energy()
+        );
+    }
+
+    Ok(())
+}
@@ -1,8 +1,16 @@
 use common::*;
+use std::f32::consts::PI;
+use wave::states::{GaussianPacket, InitialState};
 
 mod common;
 
-struct ParticleSimulation;
+struct ParticleSimulation {
+    packet: GaussianPacket,
+}
+
+// A Gaussian packet ten grid spacings wide, moving along x with one radian per grid spacing
+const SIGMA: f32 = 10.0 * DX;
+const K: f32 = 1.0 / DX;
 
 fn main() {
     wave::run::<ParticleSimulation>();
@@ -10,22 +18,30 @@ fn main() {
 
 impl wave::Simulation for ParticleSimulation {
     fn new() -> Self {
-        ParticleSimulation
+        ParticleSimulation {
+            packet: GaussianPacket::new(0.0, 0.0, SIGMA, K, 0.0),
+        }
     }
 
     fn simulation_settings(&self) -> wave::SimulationSettings {
-        COMMON_SIMULATION_SETTINGS
+        COMMON_SIMULATION_SETTINGS.with_normalization(true)
     }
 
     fn render_settings(&self) -> wave::RenderSettings {
-        COMMON_RENDER_SETTINGS
+        // Scales the peak density of the normalized packet up to a visible height
+        wave::RenderSettings::new(
+            NUM_POINTS_X,
+            NUM_POINTS_Y,
+            Y_SCALE * 2.0 * PI * SIGMA * SIGMA,
+            XZ_SCALE,
+        )
     }
 
     fn time_scale(&self) -> f32 {
         TIME_SCALE
     }
 
-    fn psi_0(&self, x: f32, y: f32) -> (f32, f32) {
-        (1.0 - x * XZ_SCALE, 0.0)
+    fn psi_0(&self, x: f32, y: f32) -> wave::Complex {
+        self.packet.psi(x, y)
     }
 }
@@ -0,0 +1,91 @@
+use wave::potentials::{HarmonicTrap, Potential};
+use wave::states::{InitialState, Superposition};
+
+mod common;
+
+// Compares the lowest levels of a discretized 2D harmonic trap against (n + 1) h_bar omega, then
+// evolves an equal superposition of the two lowest states, whose centre oscillates at omega
+struct TrapSimulation {
+    trap: HarmonicTrap,
+    psi_0: Option<Superposition>,
+}
+
+const NUM_POINTS: usize = 96;
+const DX: f32 = 0.15;
+const DT: f32 = 0.01;
+const MASS: f32 = 1.0;
+const OMEGA: f32 = 1.0;
+
+const NUM_STATES: usize = 10;
+const STEPS: usize = 50;
+
+impl wave::Simulation for TrapSimulation {
+    fn new() -> Self {
+        TrapSimulation {
+            trap: HarmonicTrap::new(MASS, OMEGA),
+            psi_0: None,
+        }
+    }
+
+    fn simulation_settings(&self) -> wave::SimulationSettings {
+        wave::SimulationSettings::new(NUM_POINTS, NUM_POINTS, DX, DX, DT, MASS)
+            .with_units(wave::UnitSystem::Dimensionless)
+            .with_integrator(wave::Integrator::SplitStep)
+    }
+
+    fn render_settings(&self) -> wave::RenderSettings {
+        common::atomic::COMMON_RENDER_SETTINGS
+    }
+
+    fn time_scale(&self) -> f32 {
+        common::atomic::TIME_SCALE
+    }
+
+    fn psi_0(&self, x: f32, y: f32) -> wave::Complex {
+        match &self.psi_0 {
+            Some(psi_0) => psi_0.psi(x, y),
+            None => wave::Complex::ZERO,
+        }
+    }
+
+    fn potential(&self, x: f32, y: f32) -> f32 {
+        self.trap.v(x, y)
+    }
+}
+
+fn main() -> Result<(), wave::Error> {
+    let mut simulation = <TrapSimulation as wave::Simulation>::new();
+
+    let states = wave::Eigensolver::new(&simulation)?.solve(NUM_STATES)?;
+    for (i, state) in states.iter().enumerate() {
+        // Level n is n + 1 times degenerate
+        let n = ((((8 * i + 1) as f64).sqrt() - 1.0) / 2.0).floor();
+        println!(
+            "E_{} = {:.6}, exact {:.6}",
+            i,
+            state.energy(),
+            (n + 1.0) * OMEGA as f64
+        );
+    }
+
+    let amplitude = wave::Complex::from(std::f32::consts::FRAC_1_SQRT_2);
+    simulation.psi_0 = Some(
+        Superposition::new()
+            .with(amplitude, states[0].clone())
+            .with(amplitude, states[1].clone()),
+    );
+
+    let mut solver = wave::Solver::new(&simulation)?;
+    for _ in 0..10 {
+        let observables = solver.observables();
+        println!(
+            "t = {:.2}, <x> = {:+.4}, <y> = {:+.4}",
+            observables.time(),
+            observables.x(),
+            observables.y()
+        );
+        solver.step(STEPS)?;
+    }
+
+    Ok(())
+}
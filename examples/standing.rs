@@ -1,13 +1,10 @@
 use common::*;
-use std::f32::consts::PI;
+use wave::states::{BoxMode, InitialState};
 
 mod common;
 
 struct StandingWaveSimulation {
-    odd_x: bool,
-    odd_y: bool,
-    kx: f32,
-    ky: f32,
+    mode: BoxMode,
 }
 
 const DEFAULT_MODE: usize = 2;
@@ -28,41 +25,37 @@ impl wave::Simulation for StandingWaveSimulation {
         );
 
         StandingWaveSimulation {
-            odd_x: x_mode % 2 == 1,
-            odd_y: y_mode % 2 == 1,
-            kx: x_mode as f32 * PI / WIDTH,
-            ky: y_mode as f32 * PI / HEIGHT,
+            mode: BoxMode::for_grid(x_mode, y_mode, &simulation_settings()),
         }
     }
 
     fn simulation_settings(&self) -> wave::SimulationSettings {
-        COMMON_SIMULATION_SETTINGS
+        simulation_settings()
     }
 
     fn render_settings(&self) -> wave::RenderSettings {
-        COMMON_RENDER_SETTINGS
+        // Scales the peak density of a normalized mode, 4 / (width * height), up to unit height
+        wave::RenderSettings::new(
+            NUM_POINTS_X,
+            NUM_POINTS_Y,
+            Y_SCALE * WIDTH * HEIGHT / 4.0,
+            XZ_SCALE,
+        )
     }
 
     fn time_scale(&self) -> f32 {
         TIME_SCALE
     }
 
-    fn psi_0(&self, x: f32, y: f32) -> (f32, f32) {
-        (
-            if self.odd_x {
-                (self.kx * x).cos()
-            } else {
-                (self.kx * x).sin()
-            } * if self.odd_y {
-                (self.ky * y).cos()
-            } else {
-                (self.ky * y).sin()
-            },
-            0.0,
-        )
+    fn psi_0(&self, x: f32, y: f32) -> wave::Complex {
+        self.mode.psi(x, y)
     }
 }
 
+fn simulation_settings() -> wave::SimulationSettings {
+    COMMON_SIMULATION_SETTINGS.with_boundaries(wave::Boundary::Dirichlet, wave::Boundary::Dirichlet)
+}
+
 fn main() {
     wave::run::<StandingWaveSimulation>()
 }
@@ -0,0 +1,58 @@
+use common::atomic::*;
+use std::f32::consts::PI;
+use wave::potentials::{Barrier, Potential};
+use wave::states::{GaussianPacket, InitialState};
+
+mod common;
+
+struct TunnelingSimulation {
+    packet: GaussianPacket,
+    barrier: Barrier,
+}
+
+// A packet with mean energy K^2 / 2 hartree hitting a slightly higher barrier, part of it tunnelling
+// through and the rest reflecting. Both parts are absorbed at the edges of the grid.
+const SIGMA: f32 = 10.0;
+const K: f32 = 1.0;
+const BARRIER_HEIGHT: f32 = 0.6;
+const BARRIER_THICKNESS: f32 = 2.0;
+
+fn main() {
+    wave::run::<TunnelingSimulation>();
+}
+
+impl wave::Simulation for TunnelingSimulation {
+    fn new() -> Self {
+        TunnelingSimulation {
+            packet: GaussianPacket::new(-WIDTH / 4.0, 0.0, SIGMA, K, 0.0),
+            barrier: Barrier::new(0.0, BARRIER_THICKNESS, BARRIER_HEIGHT),
+        }
+    }
+
+    fn simulation_settings(&self) -> wave::SimulationSettings {
+        COMMON_SIMULATION_SETTINGS
+            .with_normalization(true)
+            .with_absorbing_layer(wave::AbsorbingLayer::new(24, 1.0))
+    }
+
+    fn render_settings(&self) -> wave::RenderSettings {
+        wave::RenderSettings::new(
+            NUM_POINTS_X,
+            NUM_POINTS_Y,
+            Y_SCALE * 2.0 * PI * SIGMA * SIGMA,
+            XZ_SCALE,
+        )
+    }
+
+    fn time_scale(&self) -> f32 {
+        TIME_SCALE
+    }
+
+    fn psi_0(&self, x: f32, y: f32) -> wave::Complex {
+        self.packet.psi(x, y)
+    }
+
+    fn potential(&self, x: f32, y: f32) -> f32 {
+        self.barrier.v(x, y)
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{
+    settings::{Settings, DIRICHLET, PERIODIC},
+    AbsorbingLayer, Real,
+};
+
+// Position of the neighbour after `i` along an axis of `n` points, `None` on a Dirichlet wall
+pub(crate) fn upper(i: usize, n: usize, boundary: u32) -> Option<usize> {
+    if i + 1 < n {
+        return Some(i + 1);
+    }
+
+    match boundary {
+        DIRICHLET => None,
+        PERIODIC => Some(0),
+        _ => Some(i),
+    }
+}
+
+// Position of the neighbour before `i` along an axis of `n` points, `None` on a Dirichlet wall
+pub(crate) fn lower(i: usize, n: usize, boundary: u32) -> Option<usize> {
+    if i > 0 {
+        return Some(i - 1);
+    }
+
+    match boundary {
+        DIRICHLET => None,
+        PERIODIC => Some(n - 1),
+        _ => Some(i),
+    }
+}
+
+// exp(-dt * W / h_bar) at every point of the grid, the decay of psi over one step due to the
+// absorbing layer
+pub(crate) fn damping<T: Real>(
+    absorbing_layer: Option<AbsorbingLayer>,
+    settings: &Settings<T>,
+) -> Vec<T> {
+    let num_points_x = settings.num_points_x as usize;
+    let num_points_y = settings.num_points_y as usize;
+
+    let absorbing_layer = match absorbing_layer {
+        Some(absorbing_layer) if absorbing_layer.width() > 0 => absorbing_layer,
+        _ => return vec![T::ONE; num_points_x * num_points_y],
+    };
+
+    let width = absorbing_layer.width();
+    let depth = |i: usize, n: usize, boundary: u32| {
+        if boundary == PERIODIC {
+            return T::ZERO;
+        }
+
+        let distance = i.min(n - 1 - i);
+        T::from_usize(width.saturating_sub(distance)) / T::from_usize(width)
+    };
+
+    let dt_h = -settings.dt_hi.im;
+    let mut damping = Vec::with_capacity(num_points_x * num_points_y);
+    for y in 0..num_points_y {
+        for x in 0..num_points_x {
+            let depth_x = depth(x, num_points_x, settings.boundary_x);
+            let depth_y = depth(y, num_points_y, settings.boundary_y);
+            let w = settings.potential_scale
+                * T::from_f32(absorbing_layer.strength())
+                * (depth_x * depth_x + depth_y * depth_y);
+
+            damping.push((-dt_h * w).exp());
+        }
+    }
+    damping
+}
+
+// Points with an infinite potential are hard walls. The potential there is replaced by zero and psi
+// is pinned to zero instead by zeroing the damping, which is otherwise the absorbing layer's.
+pub(crate) fn walls<T: Real>(potential: &mut [T], absorption: &[T], damping: &mut [T]) {
+    for ((potential, absorption), damping) in potential.iter_mut().zip(absorption).zip(damping) {
+        if potential.is_finite() {
+            *damping = *absorption;
+        } else {
+            *potential = T::ZERO;
+            *damping = T::ZERO;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        potentials::{Potential, SquareWell},
+        states::{GaussianPacket, InitialState},
+        testing::{self, TestSimulation},
+        Boundary, Error, Integrator, SimulationSettings, Solver, UnitSystem,
+    };
+
+    // A packet thrown at the side of a 20 x 20 box with hard walls
+    fn box_simulation(integrator: Integrator) -> TestSimulation {
+        TestSimulation {
+            settings: SimulationSettings::new(96, 96, 0.25, 0.25, 0.005, 1.0)
+                .with_units(UnitSystem::Dimensionless)
+                .with_integrator(integrator),
+            psi_0: |x, y| GaussianPacket::new(5.0, 0.0, 1.5, 2.0, 0.0).psi(x, y),
+            potential: |x, y, _| SquareWell::infinite(20.0, 20.0).v(x, y),
+        }
+    }
+
+    #[test]
+    fn walls_keep_the_norm() {
+        // Forward Euler is left out as it gains norm with or without walls
+        for (integrator, tolerance) in [
+            (Integrator::Leapfrog, 1e-3),
+            (Integrator::CrankNicolson, 1e-10),
+        ] {
+            let mut solver = Solver::<f64>::with_precision(&box_simulation(integrator)).unwrap();
+
+            // The first step drops the tail psi_0 has on the walls
+            solver.step(1).unwrap();
+            let initial = solver.grid().norm(solver.psi());
+
+            // Long enough for the packet to reflect off the wall at x = 10
+            solver.step(400).unwrap();
+            let drift = testing::norm_drift(solver.grid(), solver.psi(), initial);
+            assert!(
+                drift < tolerance,
+                "{:?} norm drifted by {:e}",
+                integrator,
+                drift
+            );
+        }
+    }
+
+    #[test]
+    fn split_step_rejects_walls() {
+        assert_eq!(
+            Solver::<f64>::with_precision(&box_simulation(Integrator::SplitStep)).err(),
+            Some(Error::UnsupportedWalls {
+                integrator: Integrator::SplitStep
+            })
+        );
+    }
+
+    #[test]
+    fn layer_absorbs_an_outgoing_packet() {
+        // Crank-Nicolson is exactly unitary in free space, so any loss is the layer's
+        let simulation = TestSimulation::with_integrator(Integrator::CrankNicolson);
+        let simulation = TestSimulation {
+            settings: simulation
+                .settings
+                .with_absorbing_layer(AbsorbingLayer::new(16, 1.0)),
+            psi_0: |x, y| GaussianPacket::new(0.0, 0.0, 4.0, 1.0, 0.0).psi(x, y),
+            ..simulation
+        };
+        let mut solver = Solver::<f64>::with_precision(&simulation).unwrap();
+
+        let mut norm = solver.grid().norm(solver.psi());
+        let initial = norm;
+        for _ in 0..40 {
+            solver.step(20).unwrap();
+            let next = solver.grid().norm(solver.psi());
+            assert!(next <= norm, "norm rose from {} to {}", norm, next);
+            norm = next;
+        }
+        assert!(norm < 0.1 * initial, "only fell to {}", norm / initial);
+    }
+
+    #[test]
+    fn periodic_axes_are_not_damped() {
+        let damping = |boundary_x, boundary_y| {
+            let settings = Settings::<f64>::new(
+                &SimulationSettings::new(12, 10, 1.0, 1.0, 0.1, 1.0)
+                    .with_units(UnitSystem::Dimensionless)
+                    .with_boundaries(boundary_x, boundary_y),
+            );
+            super::damping(Some(AbsorbingLayer::new(3, 1.0)), &settings)
+        };
+
+        assert!(damping(Boundary::Periodic, Boundary::Periodic)
+            .iter()
+            .all(|damping| *damping == 1.0));
+
+        // Only the rows near the y edges are damped, evenly along x
+        let damping = damping(Boundary::Periodic, Boundary::Dirichlet);
+        for row in damping.chunks(12) {
+            assert!(row.iter().all(|damping| *damping == row[0]));
+        }
+        assert!(damping[0] < 1.0);
+        assert_eq!(damping[5 * 12], 1.0);
+    }
+}
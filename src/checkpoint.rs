@@ -0,0 +1,494 @@
+use crate::{
+    current_wave::CurrentWave, settings::Settings, solver_backend, Checkpointing, Complex, Error,
+    Real, Simulation, SimulationSettings, SolverBackend, UnitSystem,
+};
+use std::io::{Read, Write};
+use std::path::Path;
+
+// Everything needed to continue a run exactly where it stopped. The potential and the absorbing
+// layer are sampled from the simulation again on restore, only the settings they depend on are
+// stored to be checked.
+//
+// Files are little endian:
+//   magic       8 bytes, "WAVECKPT"
+//   version     u32
+//   precision   u32, 4 for f32 or 8 for f64 wave values
+//   settings    num_points_x u64, num_points_y u64, dx f32, dy f32, dt f32, mass f32,
+//               integrator u32, boundary_x u32, boundary_y u32, units u32,
+//               absorbing layer width u64 and strength f32, both 0 without a layer,
+//               normalize u8, potential update interval u64, u64::MAX if never updated
+//   steps       u64
+//   current     u32, which of the three waves is current
+//   startup     u8, 1 if the next step is the Euler startup step
+//   norm        f64, the norm of psi_0 before any normalization
+//   waves       3 x num_points_x x num_points_y complex values, re then im, row by row
+pub struct Checkpoint<T: Real = f32> {
+    pub(crate) header: Header,
+    pub(crate) steps: usize,
+    pub(crate) startup: bool,
+    pub(crate) initial_norm: T,
+    pub(crate) current_wave: CurrentWave,
+    pub(crate) waves: [Vec<Complex<T>>; 3],
+}
+
+// The settings a checkpoint can only be restored into if they match
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct Header {
+    num_points_x: u64,
+    num_points_y: u64,
+    dx: f32,
+    dy: f32,
+    dt: f32,
+    mass: f32,
+    integrator: u32,
+    boundary_x: u32,
+    boundary_y: u32,
+    units: u32,
+    absorbing_width: u64,
+    absorbing_strength: f32,
+    normalize: bool,
+    potential_update_interval: u64,
+}
+
+const MAGIC: [u8; 8] = *b"WAVECKPT";
+const VERSION: u32 = 2;
+
+// Everything before the waves: magic, version, precision, settings, steps, current, startup and
+// norm
+const HEADER_SIZE: u64 = 8 + 4 + 4 + 2 * 8 + 4 * 4 + 4 * 4 + 8 + 4 + 1 + 8 + 8 + 4 + 1 + 8;
+
+impl<T: Real> Checkpoint<T> {
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn time(&self) -> f64 {
+        solver_backend::time(self.steps, self.header.dt)
+    }
+
+    pub fn initial_norm(&self) -> T {
+        self.initial_norm
+    }
+
+    // psi at the checkpoint
+    pub fn psi(&self) -> &[Complex<T>] {
+        &self.waves[match self.current_wave {
+            CurrentWave::Wave1 => 0,
+            CurrentWave::Wave2 => 1,
+            CurrentWave::Wave3 => 2,
+        }]
+    }
+
+    // Writes to a temporary file first and then renames it over `path`, so a run killed mid write
+    // leaves the previous checkpoint intact
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        let file = std::fs::File::create(&temporary).map_err(io_error)?;
+        let mut file = std::io::BufWriter::new(file);
+        self.write_to(&mut file)
+            .and_then(|_| file.flush())
+            .map_err(io_error)?;
+        drop(file);
+
+        std::fs::rename(&temporary, path).map_err(io_error)
+    }
+
+    // Reads a checkpoint written in either precision, converting the waves to `T`. A file that is
+    // truncated, corrupt or of an unknown version is an `Error::InvalidCheckpoint`.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = std::fs::File::open(path).map_err(io_error)?;
+        let length = file.metadata().map_err(io_error)?.len();
+        Checkpoint::read_from(&mut std::io::BufReader::new(file), length)
+    }
+
+    fn write_to<W: Write>(&self, file: &mut W) -> std::io::Result<()> {
+        let header = &self.header;
+        let double = std::mem::size_of::<T>() == 8;
+
+        file.write_all(&MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        file.write_all(&(if double { 8u32 } else { 4u32 }).to_le_bytes())?;
+
+        file.write_all(&header.num_points_x.to_le_bytes())?;
+        file.write_all(&header.num_points_y.to_le_bytes())?;
+        for value in [header.dx, header.dy, header.dt, header.mass] {
+            file.write_all(&value.to_le_bytes())?;
+        }
+        for value in [
+            header.integrator,
+            header.boundary_x,
+            header.boundary_y,
+            header.units,
+        ] {
+            file.write_all(&value.to_le_bytes())?;
+        }
+        file.write_all(&header.absorbing_width.to_le_bytes())?;
+        file.write_all(&header.absorbing_strength.to_le_bytes())?;
+        file.write_all(&[header.normalize as u8])?;
+        file.write_all(&header.potential_update_interval.to_le_bytes())?;
+
+        file.write_all(&(self.steps as u64).to_le_bytes())?;
+        let current_wave: u32 = match self.current_wave {
+            CurrentWave::Wave1 => 0,
+            CurrentWave::Wave2 => 1,
+            CurrentWave::Wave3 => 2,
+        };
+        file.write_all(&current_wave.to_le_bytes())?;
+        file.write_all(&[self.startup as u8])?;
+        file.write_all(&self.initial_norm.to_f64().to_le_bytes())?;
+
+        for wave in &self.waves {
+            for psi in wave {
+                for value in [psi.re, psi.im] {
+                    if double {
+                        file.write_all(&value.to_f64().to_le_bytes())?;
+                    } else {
+                        file.write_all(&value.to_f32().to_le_bytes())?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_from<R: Read>(file: &mut R, length: u64) -> Result<Self, Error> {
+        let magic: [u8; 8] = read_bytes(file)?;
+        if magic != MAGIC || read_u32(file)? != VERSION {
+            return Err(Error::InvalidCheckpoint);
+        }
+
+        let double = match read_u32(file)? {
+            4 => false,
+            8 => true,
+            _ => return Err(Error::InvalidCheckpoint),
+        };
+
+        let header = Header {
+            num_points_x: read_u64(file)?,
+            num_points_y: read_u64(file)?,
+            dx: read_f32(file)?,
+            dy: read_f32(file)?,
+            dt: read_f32(file)?,
+            mass: read_f32(file)?,
+            integrator: read_u32(file)?,
+            boundary_x: read_u32(file)?,
+            boundary_y: read_u32(file)?,
+            units: read_u32(file)?,
+            absorbing_width: read_u64(file)?,
+            absorbing_strength: read_f32(file)?,
+            normalize: read_bytes::<1, R>(file)?[0] != 0,
+            potential_update_interval: read_u64(file)?,
+        };
+
+        let steps = read_u64(file)? as usize;
+        let current_wave = match read_u32(file)? {
+            0 => CurrentWave::Wave1,
+            1 => CurrentWave::Wave2,
+            2 => CurrentWave::Wave3,
+            _ => return Err(Error::InvalidCheckpoint),
+        };
+        let startup: [u8; 1] = read_bytes(file)?;
+        let initial_norm = T::from_f64(read_f64(file)?);
+
+        // The sizes are checked against the length of the file before anything is allocated for them
+        let value_size = if double { 8 } else { 4 };
+        let num_points = header
+            .num_points_x
+            .checked_mul(header.num_points_y)
+            .filter(|num_points| *num_points > 0);
+        let data_size = num_points.and_then(|num_points| num_points.checked_mul(6 * value_size));
+        if data_size.and_then(|data_size| data_size.checked_add(HEADER_SIZE)) != Some(length) {
+            return Err(Error::InvalidCheckpoint);
+        }
+        let num_points =
+            usize::try_from(num_points.unwrap_or(0)).map_err(|_| Error::InvalidCheckpoint)?;
+
+        let mut read_wave = || -> Result<Vec<Complex<T>>, Error> {
+            let mut wave = Vec::with_capacity(num_points);
+            for _ in 0..num_points {
+                let psi = if double {
+                    Complex::new(read_f64(file)?, read_f64(file)?).cast()
+                } else {
+                    Complex::new(read_f32(file)?, read_f32(file)?).cast()
+                };
+                wave.push(psi);
+            }
+            Ok(wave)
+        };
+        let waves = [read_wave()?, read_wave()?, read_wave()?];
+
+        Ok(Checkpoint {
+            header,
+            steps,
+            startup: startup[0] != 0,
+            initial_norm,
+            current_wave,
+            waves,
+        })
+    }
+
+    // Fails unless the checkpoint was taken with the same settings as `header`
+    pub(crate) fn check(&self, header: &Header) -> Result<(), Error> {
+        if self.header == *header {
+            Ok(())
+        } else {
+            Err(Error::IncompatibleCheckpoint)
+        }
+    }
+}
+
+impl Header {
+    pub(crate) fn new(settings: &SimulationSettings) -> Self {
+        let converted = Settings::<f32>::new(settings);
+        // A layer of width 0 is no layer, whatever its strength
+        let absorbing_layer = settings
+            .absorbing_layer()
+            .filter(|absorbing_layer| absorbing_layer.width() > 0);
+
+        Header {
+            num_points_x: settings.num_points_x() as u64,
+            num_points_y: settings.num_points_y() as u64,
+            dx: settings.dx(),
+            dy: settings.dy(),
+            dt: settings.dt(),
+            mass: settings.mass(),
+            integrator: converted.integrator,
+            boundary_x: converted.boundary_x,
+            boundary_y: converted.boundary_y,
+            units: match settings.units() {
+                UnitSystem::Si => 0,
+                UnitSystem::Atomic => 1,
+                UnitSystem::Dimensionless => 2,
+            },
+            absorbing_width: absorbing_layer.map_or(0, |layer| layer.width() as u64),
+            absorbing_strength: absorbing_layer.map_or(0.0, |layer| layer.strength()),
+            normalize: settings.normalize(),
+            potential_update_interval: settings
+                .potential_update_interval()
+                .map_or(u64::MAX, |interval| interval as u64),
+        }
+    }
+}
+
+// Restores the backend from the checkpoint file if the settings ask to resume and one exists,
+// returning whether it did. A time dependent potential is resampled at the last update before the
+// checkpoint, as the run had it.
+pub(crate) fn resume<C, T: Real, B: SolverBackend<C, T> + ?Sized, S: Simulation>(
+    checkpointing: Checkpointing,
+    simulation: &S,
+    backend: &mut B,
+    context: &mut C,
+) -> Result<bool, Error> {
+    if !checkpointing.resume() || !Path::new(checkpointing.path()).exists() {
+        return Ok(false);
+    }
+
+    let checkpoint = Checkpoint::read(checkpointing.path())?;
+    backend.restore(&checkpoint, context)?;
+
+    if let Some(interval) = simulation.simulation_settings().potential_update_interval() {
+        let steps = backend.steps();
+        let t = solver_backend::time(steps - steps % interval.max(1), backend.dt()) as f32;
+        let potential = backend
+            .grid()
+            .sample(|x, y| T::from_f32(simulation.time_dependent_potential(x, y, t)));
+        backend.set_potential(&potential, context)?;
+    }
+
+    Ok(true)
+}
+
+// Writes a checkpoint if one is due
+pub(crate) fn update<C, T: Real, B: SolverBackend<C, T> + ?Sized>(
+    checkpointing: Checkpointing,
+    backend: &mut B,
+    context: &mut C,
+) -> Result<(), Error> {
+    let interval = checkpointing.interval();
+    if interval == 0 || !backend.steps().is_multiple_of(interval) {
+        return Ok(());
+    }
+
+    backend.checkpoint(context).write(checkpointing.path())
+}
+
+fn io_error(error: std::io::Error) -> Error {
+    Error::Io { kind: error.kind() }
+}
+
+// Running out of bytes means the file is truncated rather than unreadable
+fn read_bytes<const N: usize, R: Read>(file: &mut R) -> Result<[u8; N], Error> {
+    let mut bytes = [0; N];
+    file.read_exact(&mut bytes)
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => Error::InvalidCheckpoint,
+            kind => Error::Io { kind },
+        })?;
+    Ok(bytes)
+}
+
+fn read_u32<R: Read>(file: &mut R) -> Result<u32, Error> {
+    read_bytes(file).map(u32::from_le_bytes)
+}
+
+fn read_u64<R: Read>(file: &mut R) -> Result<u64, Error> {
+    read_bytes(file).map(u64::from_le_bytes)
+}
+
+fn read_f32<R: Read>(file: &mut R) -> Result<f32, Error> {
+    read_bytes(file).map(f32::from_le_bytes)
+}
+
+fn read_f64<R: Read>(file: &mut R) -> Result<f64, Error> {
+    read_bytes(file).map(f64::from_le_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        states::{GaussianPacket, InitialState},
+        testing::TestSimulation,
+        AbsorbingLayer, Integrator, Solver,
+    };
+    use std::path::PathBuf;
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("wave-{}-{}.checkpoint", name, std::process::id()))
+    }
+
+    fn simulation(integrator: Integrator) -> TestSimulation {
+        TestSimulation {
+            psi_0: |x, y| GaussianPacket::new(0.0, 0.0, 6.0, 0.5, 0.0).psi(x, y),
+            potential: |x, _, _| 1e-3 * x * x,
+            ..TestSimulation::with_integrator(integrator)
+        }
+    }
+
+    #[test]
+    fn resumes_bit_identically() {
+        for integrator in [
+            Integrator::ForwardEuler,
+            Integrator::Leapfrog,
+            Integrator::CrankNicolson,
+            Integrator::SplitStep,
+        ] {
+            let path = path("resume");
+            let simulation = simulation(integrator);
+
+            let mut original = Solver::<f64>::with_precision(&simulation).unwrap();
+            original.step(7).unwrap();
+            original.checkpoint().write(&path).unwrap();
+            original.step(20).unwrap();
+
+            let mut resumed = Solver::<f64>::with_precision(&simulation).unwrap();
+            resumed.restore(&Checkpoint::read(&path).unwrap()).unwrap();
+            assert_eq!(resumed.steps(), 7);
+            resumed.step(20).unwrap();
+
+            assert_eq!(original.psi(), resumed.psi(), "{:?}", integrator);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_other_settings() {
+        let simulation = simulation(Integrator::Leapfrog);
+        let checkpoint = Solver::<f64>::with_precision(&simulation)
+            .unwrap()
+            .checkpoint();
+
+        for settings in [
+            simulation.settings.with_integrator(Integrator::SplitStep),
+            simulation
+                .settings
+                .with_absorbing_layer(AbsorbingLayer::new(4, 1.0)),
+            simulation.settings.with_normalization(true),
+            simulation.settings.with_potential_update_interval(10),
+        ] {
+            let other = TestSimulation {
+                settings,
+                ..simulation
+            };
+            let mut solver = Solver::<f64>::with_precision(&other).unwrap();
+            assert_eq!(
+                solver.restore(&checkpoint).err(),
+                Some(Error::IncompatibleCheckpoint),
+                "{:?}",
+                settings
+            );
+        }
+
+        // A layer of width 0 is the same as none
+        let other = TestSimulation {
+            settings: simulation
+                .settings
+                .with_absorbing_layer(AbsorbingLayer::new(0, 1.0)),
+            ..simulation
+        };
+        let mut solver = Solver::<f64>::with_precision(&other).unwrap();
+        assert_eq!(solver.restore(&checkpoint), Ok(()));
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        let path = path("corrupt");
+        let mut solver = Solver::new(&simulation(Integrator::Leapfrog)).unwrap();
+        solver.checkpoint().write(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        let corrupt = |offset: usize, value: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[offset..offset + value.len()].copy_from_slice(value);
+            bytes
+        };
+        let files = [
+            corrupt(0, b"NOTACKPT"),
+            corrupt(8, &(VERSION - 1).to_le_bytes()),
+            corrupt(12, &3u32.to_le_bytes()),
+            // num_points_x and num_points_y
+            corrupt(16, &u64::MAX.to_le_bytes()),
+            corrupt(
+                16,
+                &[(1u64 << 20).to_le_bytes(), (1u64 << 20).to_le_bytes()].concat(),
+            ),
+            corrupt(16, &0u64.to_le_bytes()),
+            bytes[..bytes.len() - 1].to_vec(),
+            bytes[..40].to_vec(),
+            [bytes.as_slice(), &[0]].concat(),
+        ];
+
+        for file in files {
+            std::fs::write(&path, file).unwrap();
+            assert_eq!(
+                Checkpoint::<f32>::read(&path).err(),
+                Some(Error::InvalidCheckpoint)
+            );
+        }
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            Checkpoint::<f32>::read(&path),
+            Err(Error::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn converts_precision() {
+        let path = path("precision");
+        let mut solver = Solver::<f64>::with_precision(&simulation(Integrator::Leapfrog)).unwrap();
+        solver.step(3).unwrap();
+        solver.checkpoint().write(&path).unwrap();
+
+        let checkpoint = Checkpoint::<f32>::read(&path).unwrap();
+        assert_eq!(checkpoint.steps(), 3);
+        for (single, double) in checkpoint.psi().iter().zip(solver.psi()) {
+            assert_eq!(*single, double.cast::<f32>());
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
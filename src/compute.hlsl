@@ -2,17 +2,31 @@ cbuffer Settings {
     float2 DTH_2MI; // dt * h_bar / (2 * m * i)
     float DX2; // dx ^ 2
     float DY2; // dy ^ 2
+    float2 DT_HI; // dt / (h_bar * i)
     uint NUM_POINTS_X;
     uint NUM_POINTS_Y;
-    float2 reserved;
+    uint INTEGRATOR;
+    uint BOUNDARY_X;
+    uint BOUNDARY_Y;
+    float POTENTIAL_SCALE; // converts potentials into hartrees
 }
 
+static const uint FORWARD_EULER = 0;
+static const uint LEAPFROG = 1;
+
+static const uint NEUMANN = 0;
+static const uint DIRICHLET = 1;
+static const uint PERIODIC = 2;
+
 RWStructuredBuffer<float2> previous_wave : register(u0);
 RWStructuredBuffer<float2> current_wave : register(u1);
 RWStructuredBuffer<float2> next_wave : register(u2);
 
 RWTexture2D<float> output: register(u3);
 
+RWStructuredBuffer<float> potential : register(u4);
+RWStructuredBuffer<float> damping : register(u5);
+
 uint index(uint x, uint y) {
     return x + y * NUM_POINTS_X;
 }
@@ -21,21 +35,55 @@ float2 c_mul(float2 c1, float2 c2) {
 	return float2(c1.x  *c2.x - c1.y * c2.y, c1.y * c2.x + c1.x * c2.y);
 }
 
+// Finds the neighbour `offset` away from `i` along an axis of `n` points. Returns false if the
+// neighbour lies on a Dirichlet wall.
+bool neighbour(uint i, int offset, uint n, uint boundary, out uint j) {
+    int k = int(i) + offset;
+    j = i;
+
+    if (k >= 0 && k < int(n)) {
+        j = uint(k);
+    } else if (boundary == DIRICHLET) {
+        return false;
+    } else if (boundary == PERIODIC) {
+        j = uint((k + int(n)) % int(n));
+    }
+
+    return true;
+}
+
+float2 neighbour_x(uint2 tid, int offset) {
+    uint x;
+    if (!neighbour(tid.x, offset, NUM_POINTS_X, BOUNDARY_X, x)) {
+        return float2(0.0, 0.0);
+    }
+    return current_wave[index(x, tid.y)];
+}
+
+float2 neighbour_y(uint2 tid, int offset) {
+    uint y;
+    if (!neighbour(tid.y, offset, NUM_POINTS_Y, BOUNDARY_Y, y)) {
+        return float2(0.0, 0.0);
+    }
+    return current_wave[index(tid.x, y)];
+}
+
 [numthreads(16,16,1)]
 void compute_main(uint3 tid : SV_DispatchThreadID) {
+    // The last thread groups along each axis may hang over the edge of the grid
+    if (tid.x >= NUM_POINTS_X || tid.y >= NUM_POINTS_Y) {
+        return;
+    }
+
     uint idx = index(tid.x, tid.y);
-    uint x_u_index = tid.x == NUM_POINTS_X - 1 ? idx : index(tid.x + 1, tid.y);
-    uint x_l_index = tid.x == 0 ? idx : index(tid.x - 1, tid.y);
-    uint y_u_index = tid.y == NUM_POINTS_Y - 1 ? idx : index(tid.x, tid.y + 1);
-    uint y_l_index = tid.y == 0 ? idx : index(tid.x, tid.y - 1);
 
     // Gather values
     float2 psi_now = current_wave[idx];
     float2 psi_prev = previous_wave[idx];
-    float2 psi_x_u = current_wave[x_u_index];
-    float2 psi_x_l = current_wave[x_l_index];
-    float2 psi_y_u = current_wave[y_u_index];
-    float2 psi_y_l = current_wave[y_l_index];
+    float2 psi_x_u = neighbour_x(tid.xy, 1);
+    float2 psi_x_l = neighbour_x(tid.xy, -1);
+    float2 psi_y_u = neighbour_y(tid.xy, 1);
+    float2 psi_y_l = neighbour_y(tid.xy, -1);
 
     // Compute new value
     float2 psi_now_2 = 2.0 * psi_now;
@@ -43,8 +91,17 @@ void compute_main(uint3 tid : SV_DispatchThreadID) {
     float2 new_psi_x = (psi_x_u - psi_now_2 + psi_x_l) / DX2;
     float2 new_psi_y = (psi_y_u - psi_now_2 + psi_y_l) / DY2;
     float2 psi_grad = c_mul(DTH_2MI, new_psi_x + new_psi_y);
+    float2 psi_potential = c_mul(DT_HI, POTENTIAL_SCALE * potential[idx] * psi_now);
+    float2 psi_step = psi_potential - psi_grad; // dt * H psi / (i * h_bar)
 
-    float2 psi_new = psi_now - psi_grad;
+    // The absorbing layer is integrated exactly, psi decaying by `d` every step
+    float d = damping[idx];
+    float2 psi_new;
+    if (INTEGRATOR == LEAPFROG) {
+        psi_new = d * d * psi_prev + 2.0 * d * psi_step;
+    } else {
+        psi_new = d * (psi_now + psi_step);
+    }
 
     // Set new value
     next_wave[idx] = psi_new;
@@ -0,0 +1,405 @@
+use crate::{
+    boundary,
+    checkpoint::Header,
+    crank_nicolson::CrankNicolson,
+    current_wave::CurrentWave,
+    diagnostics, observables,
+    settings::{Settings, CRANK_NICOLSON, LEAPFROG, SPLIT_STEP},
+    solver_backend::sample_psi_0,
+    split_step::SplitStep,
+    Checkpoint, Complex, Density, Error, Grid, Integrator, Measurement, Observables, Real,
+    Simulation, SolverBackend,
+};
+
+enum Scheme<T> {
+    Explicit,
+    CrankNicolson(CrankNicolson<T>),
+    SplitStep(SplitStep<T>),
+}
+
+// The explicit scheme mirrors `compute.hlsl` point for point so both backends step identically. `T`
+// is the precision everything is computed and stored in.
+pub struct CpuSimulationRunner<T: Real = f32> {
+    grid: Grid,
+    dt: f32,
+    h_bar: f64,
+    steps: usize,
+    initial_norm: T,
+    settings: Settings<T>,
+    scheme: Scheme<T>,
+    header: Header,
+    current_wave: CurrentWave,
+    startup: bool,
+
+    // Wave buffers
+    wave1: Vec<Complex<T>>,
+    wave2: Vec<Complex<T>>,
+    wave3: Vec<Complex<T>>,
+
+    output: Vec<T>,
+
+    potential: Vec<T>,
+    absorption: Vec<T>,
+    damping: Vec<T>,
+}
+
+impl<T: Real> CpuSimulationRunner<T> {
+    pub fn new<S: Simulation>(simulation: &S) -> Result<Self, Error> {
+        let simulation_settings = simulation.simulation_settings();
+
+        let grid = Grid::new(&simulation_settings)?;
+
+        let (wave_values, initial_norm) = sample_psi_0(&grid, simulation);
+        let output = wave_values.iter().map(|psi| psi.norm_sqr()).collect();
+
+        let mut potential =
+            grid.sample(|x, y| T::from_f32(simulation.time_dependent_potential(x, y, 0.0)));
+
+        let settings = Settings::new(&simulation_settings);
+        check_walls(&settings, &potential)?;
+        let scheme = match settings.integrator {
+            CRANK_NICOLSON => Scheme::CrankNicolson(CrankNicolson::new(&settings)),
+            SPLIT_STEP => Scheme::SplitStep(SplitStep::new(&settings)),
+            _ => Scheme::Explicit,
+        };
+        let absorption = boundary::damping(simulation_settings.absorbing_layer(), &settings);
+        let mut damping = absorption.clone();
+        boundary::walls(&mut potential, &absorption, &mut damping);
+
+        Ok(CpuSimulationRunner {
+            grid,
+            dt: simulation_settings.dt(),
+            h_bar: simulation_settings.units().h_bar(),
+            settings,
+            scheme,
+            header: Header::new(&simulation_settings),
+            steps: 0,
+            initial_norm,
+            current_wave: CurrentWave::Wave1,
+            startup: true,
+            wave1: wave_values.clone(),
+            wave2: wave_values.clone(),
+            wave3: wave_values,
+            output,
+            potential,
+            absorption,
+            damping,
+        })
+    }
+
+    pub(crate) fn current_wave(&self) -> &[Complex<T>] {
+        match self.current_wave {
+            CurrentWave::Wave1 => &self.wave1,
+            CurrentWave::Wave2 => &self.wave2,
+            CurrentWave::Wave3 => &self.wave3,
+        }
+    }
+
+    pub(crate) fn output(&self) -> &[T] {
+        &self.output
+    }
+}
+
+impl<C, T: Real> SolverBackend<C, T> for CpuSimulationRunner<T> {
+    fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    fn steps(&self) -> usize {
+        self.steps
+    }
+
+    fn initial_norm(&self) -> T {
+        self.initial_norm
+    }
+
+    fn step(&mut self, _: &mut C) {
+        let settings = if self.startup {
+            self.settings.startup()
+        } else {
+            self.settings
+        };
+
+        let (previous_wave, current_wave, next_wave) = match self.current_wave {
+            CurrentWave::Wave1 => (&self.wave3, &self.wave1, &mut self.wave2),
+            CurrentWave::Wave2 => (&self.wave1, &self.wave2, &mut self.wave3),
+            CurrentWave::Wave3 => (&self.wave2, &self.wave3, &mut self.wave1),
+        };
+
+        match &mut self.scheme {
+            Scheme::Explicit => explicit_step(
+                &settings,
+                previous_wave,
+                current_wave,
+                next_wave,
+                &self.potential,
+                &self.damping,
+                &mut self.output,
+            ),
+            Scheme::CrankNicolson(crank_nicolson) => {
+                crank_nicolson.step(current_wave, next_wave, &self.potential, &self.damping);
+                damp(next_wave, &self.damping);
+                write_output(next_wave, &mut self.output);
+            }
+            Scheme::SplitStep(split_step) => {
+                split_step.step(current_wave, next_wave, &self.potential);
+                damp(next_wave, &self.damping);
+                write_output(next_wave, &mut self.output);
+            }
+        }
+
+        self.current_wave = self.current_wave.next();
+        self.steps += 1;
+        self.startup = false;
+    }
+
+    fn density(&mut self, _: &mut C) -> Density<'_, T> {
+        Density::Values(&self.output)
+    }
+
+    fn psi(&mut self, _: &mut C) -> Vec<Complex<T>> {
+        self.current_wave().to_vec()
+    }
+
+    fn set_psi(&mut self, psi: &[Complex<T>], _: &mut C) -> Result<(), Error> {
+        self.grid.check_size(psi.len())?;
+
+        self.wave1.copy_from_slice(psi);
+        self.wave2.copy_from_slice(psi);
+        self.wave3.copy_from_slice(psi);
+        self.startup = true;
+        write_output(psi, &mut self.output);
+        Ok(())
+    }
+
+    fn measure(&mut self, _: &mut C) -> Measurement<T> {
+        diagnostics::measure(
+            &self.grid,
+            &self.settings,
+            self.current_wave(),
+            &self.potential,
+            self.steps,
+            SolverBackend::<C, T>::time(self),
+        )
+    }
+
+    fn observables(&mut self, _: &mut C) -> Observables {
+        Observables::from_sums(
+            observables::sums(&self.settings, self.current_wave()),
+            &self.grid,
+            self.h_bar,
+            self.steps,
+            SolverBackend::<C, T>::time(self),
+        )
+    }
+
+    fn set_potential(&mut self, potential: &[T], _: &mut C) -> Result<(), Error> {
+        self.grid.check_size(potential.len())?;
+        check_walls(&self.settings, potential)?;
+        self.potential.copy_from_slice(potential);
+        boundary::walls(&mut self.potential, &self.absorption, &mut self.damping);
+        Ok(())
+    }
+
+    fn checkpoint(&mut self, _: &mut C) -> Checkpoint<T> {
+        Checkpoint {
+            header: self.header,
+            steps: self.steps,
+            startup: self.startup,
+            initial_norm: self.initial_norm,
+            current_wave: self.current_wave,
+            waves: [self.wave1.clone(), self.wave2.clone(), self.wave3.clone()],
+        }
+    }
+
+    fn restore(&mut self, checkpoint: &Checkpoint<T>, _: &mut C) -> Result<(), Error> {
+        checkpoint.check(&self.header)?;
+
+        let [wave1, wave2, wave3] = &checkpoint.waves;
+        self.wave1.copy_from_slice(wave1);
+        self.wave2.copy_from_slice(wave2);
+        self.wave3.copy_from_slice(wave3);
+        self.current_wave = checkpoint.current_wave;
+        self.steps = checkpoint.steps;
+        self.startup = checkpoint.startup;
+        self.initial_norm = checkpoint.initial_norm;
+        write_output(checkpoint.psi(), &mut self.output);
+
+        Ok(())
+    }
+}
+
+// The split step's transforms couple every point of a line, so it has no way to pin psi to zero on
+// a wall inside the grid
+fn check_walls<T: Real>(settings: &Settings<T>, potential: &[T]) -> Result<(), Error> {
+    if settings.integrator == SPLIT_STEP && potential.iter().any(|v| !v.is_finite()) {
+        return Err(Error::UnsupportedWalls {
+            integrator: Integrator::SplitStep,
+        });
+    }
+
+    Ok(())
+}
+
+pub(crate) fn explicit_step<T: Real>(
+    settings: &Settings<T>,
+    previous_wave: &[Complex<T>],
+    current_wave: &[Complex<T>],
+    next_wave: &mut [Complex<T>],
+    potential: &[T],
+    damping: &[T],
+    output: &mut [T],
+) {
+    let num_points_x = settings.num_points_x as usize;
+    let num_points_y = settings.num_points_y as usize;
+
+    for y in 0..num_points_y {
+        for x in 0..num_points_x {
+            let idx = x + y * num_points_x;
+            let neighbour_x =
+                |x: Option<usize>| x.map_or(Complex::ZERO, |x| current_wave[x + y * num_points_x]);
+            let neighbour_y =
+                |y: Option<usize>| y.map_or(Complex::ZERO, |y| current_wave[x + y * num_points_x]);
+
+            // Gather values
+            let psi_now = current_wave[idx];
+            let psi_prev = previous_wave[idx];
+            let psi_x_u = neighbour_x(boundary::upper(x, num_points_x, settings.boundary_x));
+            let psi_x_l = neighbour_x(boundary::lower(x, num_points_x, settings.boundary_x));
+            let psi_y_u = neighbour_y(boundary::upper(y, num_points_y, settings.boundary_y));
+            let psi_y_l = neighbour_y(boundary::lower(y, num_points_y, settings.boundary_y));
+
+            // Compute new value
+            let psi_now_2 = psi_now * T::from_f32(2.0);
+
+            let new_psi_x = (psi_x_u - psi_now_2 + psi_x_l) / settings.dx2;
+            let new_psi_y = (psi_y_u - psi_now_2 + psi_y_l) / settings.dy2;
+            let psi_grad = settings.dth_2mi * (new_psi_x + new_psi_y);
+            let psi_potential =
+                settings.dt_hi * psi_now * (settings.potential_scale * potential[idx]);
+            let psi_step = psi_potential - psi_grad; // dt * H psi / (i * h_bar)
+
+            // The absorbing layer is integrated exactly, psi decaying by `damping` every step
+            let damping = damping[idx];
+            let psi_new = if settings.integrator == LEAPFROG {
+                psi_prev * (damping * damping) + psi_step * (T::from_f32(2.0) * damping)
+            } else {
+                (psi_now + psi_step) * damping
+            };
+
+            // Set new value
+            next_wave[idx] = psi_new;
+            output[idx] = psi_new.norm_sqr();
+        }
+    }
+}
+
+fn damp<T: Real>(wave: &mut [Complex<T>], damping: &[T]) {
+    for (psi, damping) in wave.iter_mut().zip(damping) {
+        *psi *= *damping;
+    }
+}
+
+fn write_output<T: Real>(wave: &[Complex<T>], output: &mut [T]) {
+    for (output, psi) in output.iter_mut().zip(wave) {
+        *output = psi.norm_sqr();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        states::{GaussianPacket, InitialState},
+        testing::{self, TestSimulation},
+        Boundary, Integrator, SimulationSettings, Solver, UnitSystem,
+    };
+
+    // One forward Euler step of psi = [1, 2, 4] along x with V = [0, 1, 0], dx = 1, dt = 0.1 and
+    // h_bar = m = 1, so psi_new = psi + i * dt * (psi'' / 2 - V * psi)
+    fn step(boundary: Boundary) -> Vec<Complex<f64>> {
+        let settings = Settings::new(
+            &SimulationSettings::new(3, 1, 1.0, 1.0, 0.1, 1.0)
+                .with_units(UnitSystem::Dimensionless)
+                .with_integrator(Integrator::ForwardEuler)
+                .with_boundaries(boundary, Boundary::Neumann),
+        );
+
+        let psi = [1.0, 2.0, 4.0].map(Complex::from);
+        let mut next = vec![Complex::ZERO; 3];
+        let mut output = vec![0.0; 3];
+        explicit_step(
+            &settings,
+            &psi,
+            &psi,
+            &mut next,
+            &[0.0, 1.0, 0.0],
+            &[1.0; 3],
+            &mut output,
+        );
+
+        for (output, psi) in output.iter().zip(&next) {
+            assert_eq!(*output, psi.norm_sqr());
+        }
+        next
+    }
+
+    fn assert_close(actual: &[Complex<f64>], expected: [(f64, f64); 3]) {
+        for (actual, (re, im)) in actual.iter().zip(expected) {
+            assert!(
+                (actual.re - re).abs() < 1e-7 && (actual.im - im).abs() < 1e-7,
+                "{:?} != {} + {}i",
+                actual,
+                re,
+                im
+            );
+        }
+    }
+
+    #[test]
+    fn explicit_step_neumann() {
+        // psi'' = [1, 1, -2], the edges mirroring themselves
+        assert_close(
+            &step(Boundary::Neumann),
+            [(1.0, 0.05), (2.0, -0.15), (4.0, -0.1)],
+        );
+    }
+
+    #[test]
+    fn explicit_step_dirichlet() {
+        // psi'' = [0, 1, -6], zero past the edges
+        assert_close(
+            &step(Boundary::Dirichlet),
+            [(1.0, 0.0), (2.0, -0.15), (4.0, -0.3)],
+        );
+    }
+
+    #[test]
+    fn explicit_step_periodic() {
+        // psi'' = [4, 1, -5], the edges wrapping around
+        assert_close(
+            &step(Boundary::Periodic),
+            [(1.0, 0.2), (2.0, -0.15), (4.0, -0.25)],
+        );
+    }
+
+    #[test]
+    fn leapfrog_keeps_the_norm() {
+        let simulation = TestSimulation {
+            psi_0: |x, y| GaussianPacket::new(0.0, 0.0, 6.0, 0.5, 0.0).psi(x, y),
+            ..TestSimulation::with_integrator(Integrator::Leapfrog)
+        };
+        let mut solver = Solver::<f64>::with_precision(&simulation).unwrap();
+        let initial = solver.grid().norm(solver.psi());
+
+        for _ in 0..20 {
+            solver.step(20).unwrap();
+            let drift = testing::norm_drift(solver.grid(), solver.psi(), initial);
+            assert!(drift < 1e-3, "norm drifted by {:e}", drift);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{
+    boundary,
+    settings::{Settings, DIRICHLET, PERIODIC},
+    Complex, Real,
+};
+
+// Peaceman-Rachford ADI splitting of (1 + a H) psi_{n+1} = (1 - a H) psi_n with a = i * dt / (2 * h_bar):
+//   (1 + a H_x) psi_* = (1 - a H_y) psi_n
+//   (1 + a H_y) psi_{n+1} = (1 - a H_x) psi_*
+// where a H_x psi = alpha_x * (psi_{x+1} - 2 psi + psi_{x-1}) + beta * V psi with
+// alpha_x = DTH_2MI / (2 * dx ^ 2), and beta = -DT_HI / 4 gives each direction half the potential
+// (scaled to hartrees). Hard walls are identity rows that hold psi at zero, so the lines between
+// them are solved as Dirichlet lines of their own.
+pub(crate) struct CrankNicolson<T> {
+    num_points_x: usize,
+    num_points_y: usize,
+    boundary_x: u32,
+    boundary_y: u32,
+    alpha_x: Complex<T>,
+    alpha_y: Complex<T>,
+    beta: Complex<T>,
+
+    intermediate: Vec<Complex<T>>,
+
+    // Scratch space for a single line of the grid
+    line: Vec<Complex<T>>,
+    rhs: Vec<Complex<T>>,
+    line_potential: Vec<T>,
+    line_walls: Vec<bool>,
+    diagonal: Vec<Complex<T>>,
+    c_prime: Vec<Complex<T>>,
+    correction: Vec<Complex<T>>,
+    correction_rhs: Vec<Complex<T>>,
+}
+
+impl<T: Real> CrankNicolson<T> {
+    pub fn new(settings: &Settings<T>) -> Self {
+        let num_points_x = settings.num_points_x as usize;
+        let num_points_y = settings.num_points_y as usize;
+        let line_length = num_points_x.max(num_points_y);
+
+        CrankNicolson {
+            num_points_x,
+            num_points_y,
+            boundary_x: settings.boundary_x,
+            boundary_y: settings.boundary_y,
+            alpha_x: settings.dth_2mi * (T::from_f32(0.5) / settings.dx2),
+            alpha_y: settings.dth_2mi * (T::from_f32(0.5) / settings.dy2),
+            beta: settings.dt_hi * (T::from_f32(-0.25) * settings.potential_scale),
+            intermediate: vec![Complex::ZERO; num_points_x * num_points_y],
+            line: vec![Complex::ZERO; line_length],
+            rhs: vec![Complex::ZERO; line_length],
+            line_potential: vec![T::ZERO; line_length],
+            line_walls: vec![false; line_length],
+            diagonal: vec![Complex::ZERO; line_length],
+            c_prime: vec![Complex::ZERO; line_length],
+            correction: vec![Complex::ZERO; line_length],
+            correction_rhs: vec![Complex::ZERO; line_length],
+        }
+    }
+
+    // Points where `damping` is zero are the hard walls left by `boundary::walls`
+    pub fn step(
+        &mut self,
+        current_wave: &[Complex<T>],
+        next_wave: &mut [Complex<T>],
+        potential: &[T],
+        damping: &[T],
+    ) {
+        let num_points_x = self.num_points_x;
+        let num_points_y = self.num_points_y;
+        let wall = |idx: usize| damping[idx] == T::ZERO;
+
+        // Implicit in x, explicit in y
+        for y in 0..num_points_y {
+            for x in 0..num_points_x {
+                let idx = x + y * num_points_x;
+                self.line_walls[x] = wall(idx);
+                self.line_potential[x] = potential[idx];
+                if self.line_walls[x] {
+                    self.rhs[x] = Complex::ZERO;
+                    continue;
+                }
+
+                let psi_y = second_difference(
+                    current_wave,
+                    y,
+                    num_points_y,
+                    self.boundary_y,
+                    |y| x + y * num_points_x,
+                    wall,
+                );
+                let psi_v = current_wave[idx] * potential[idx];
+                self.rhs[x] = current_wave[idx] - self.alpha_y * psi_y - self.beta * psi_v;
+            }
+
+            self.solve(self.alpha_x, num_points_x, self.boundary_x);
+
+            self.intermediate[y * num_points_x..(y + 1) * num_points_x]
+                .copy_from_slice(&self.line[..num_points_x]);
+        }
+
+        // Implicit in y, explicit in x
+        for x in 0..num_points_x {
+            for y in 0..num_points_y {
+                let idx = x + y * num_points_x;
+                self.line_walls[y] = wall(idx);
+                self.line_potential[y] = potential[idx];
+                if self.line_walls[y] {
+                    self.rhs[y] = Complex::ZERO;
+                    continue;
+                }
+
+                let psi_x = second_difference(
+                    &self.intermediate,
+                    x,
+                    num_points_x,
+                    self.boundary_x,
+                    |x| x + y * num_points_x,
+                    wall,
+                );
+                let psi_v = self.intermediate[idx] * potential[idx];
+                self.rhs[y] = self.intermediate[idx] - self.alpha_x * psi_x - self.beta * psi_v;
+            }
+
+            self.solve(self.alpha_y, num_points_y, self.boundary_y);
+
+            for y in 0..num_points_y {
+                next_wave[x + y * num_points_x] = self.line[y];
+            }
+        }
+    }
+
+    // Solves (1 + alpha * d^2 + beta * V) line = rhs along a line of `n` points, with psi = 0 on
+    // its walls
+    fn solve(&mut self, alpha: Complex<T>, n: usize, boundary: u32) {
+        // A wall cuts a periodic line open, so it is rotated to start on the wall and solved as a
+        // Dirichlet line
+        let first_wall = self.line_walls[..n].iter().position(|wall| *wall);
+        if let (PERIODIC, Some(first_wall)) = (boundary, first_wall) {
+            self.rhs[..n].rotate_left(first_wall);
+            self.line_potential[..n].rotate_left(first_wall);
+            self.line_walls[..n].rotate_left(first_wall);
+            self.solve(alpha, n, DIRICHLET);
+            self.line[..n].rotate_right(first_wall);
+            return;
+        }
+
+        for i in 0..n {
+            // A Neumann edge point is its own missing neighbour
+            let mut self_coupling = T::from_f32(2.0);
+            if boundary != DIRICHLET && boundary != PERIODIC {
+                if i == 0 {
+                    self_coupling -= T::ONE;
+                }
+                if i == n - 1 {
+                    self_coupling -= T::ONE;
+                }
+            }
+
+            self.diagonal[i] =
+                Complex::ONE - alpha * self_coupling + self.beta * self.line_potential[i];
+        }
+
+        if boundary != PERIODIC {
+            // The runs of points between walls don't couple to each other
+            let mut start = 0;
+            while start < n {
+                if self.line_walls[start] {
+                    self.line[start] = Complex::ZERO;
+                    start += 1;
+                    continue;
+                }
+
+                let end = self.line_walls[start..n]
+                    .iter()
+                    .position(|wall| *wall)
+                    .map_or(n, |length| start + length);
+                thomas(
+                    alpha,
+                    &self.diagonal[start..end],
+                    &self.rhs[start..end],
+                    &mut self.c_prime[start..end],
+                    &mut self.line[start..end],
+                );
+                start = end;
+            }
+            return;
+        }
+
+        match n {
+            1 => {
+                // Both neighbours are the point itself
+                let diagonal = self.diagonal[0] + alpha * T::from_f32(2.0);
+                self.line[0] = self.rhs[0] / diagonal;
+            }
+            2 => {
+                // Both neighbours are the other point
+                let off = alpha * T::from_f32(2.0);
+                let determinant = self.diagonal[0] * self.diagonal[1] - off * off;
+                self.line[0] = (self.diagonal[1] * self.rhs[0] - off * self.rhs[1]) / determinant;
+                self.line[1] = (self.diagonal[0] * self.rhs[1] - off * self.rhs[0]) / determinant;
+            }
+            _ => self.solve_cyclic(alpha, n),
+        }
+    }
+
+    // Sherman-Morrison correction of the Thomas algorithm for the corner entries that couple the
+    // first and last points of a periodic line
+    fn solve_cyclic(&mut self, alpha: Complex<T>, n: usize) {
+        let gamma = -self.diagonal[0];
+        let corner = alpha * alpha / gamma;
+
+        self.diagonal[0] -= gamma;
+        self.diagonal[n - 1] -= corner;
+
+        thomas(
+            alpha,
+            &self.diagonal[..n],
+            &self.rhs[..n],
+            &mut self.c_prime[..n],
+            &mut self.line[..n],
+        );
+
+        self.correction_rhs[..n].fill(Complex::ZERO);
+        self.correction_rhs[0] = gamma;
+        self.correction_rhs[n - 1] = alpha;
+        thomas(
+            alpha,
+            &self.diagonal[..n],
+            &self.correction_rhs[..n],
+            &mut self.c_prime[..n],
+            &mut self.correction[..n],
+        );
+
+        let numerator = self.line[0] + alpha * self.line[n - 1] / gamma;
+        let denominator =
+            Complex::ONE + self.correction[0] + alpha * self.correction[n - 1] / gamma;
+        let factor = numerator / denominator;
+
+        for i in 0..n {
+            self.line[i] -= factor * self.correction[i];
+        }
+    }
+}
+
+// Thomas algorithm for a tridiagonal system with constant off-diagonal entries
+fn thomas<T: Real>(
+    off: Complex<T>,
+    diagonal: &[Complex<T>],
+    rhs: &[Complex<T>],
+    c_prime: &mut [Complex<T>],
+    out: &mut [Complex<T>],
+) {
+    let n = diagonal.len();
+
+    c_prime[0] = off / diagonal[0];
+    out[0] = rhs[0] / diagonal[0];
+    for i in 1..n {
+        let m = diagonal[i] - off * c_prime[i - 1];
+        c_prime[i] = off / m;
+        out[i] = (rhs[i] - off * out[i - 1]) / m;
+    }
+
+    for i in (0..n - 1).rev() {
+        out[i] -= c_prime[i] * out[i + 1];
+    }
+}
+
+// psi_{i+1} - 2 psi_i + psi_{i-1} along an axis of `n` points, `index` mapping a position along
+// the axis to an index into `wave`. Neighbours on a wall count as zero.
+fn second_difference<T: Real, F: Fn(usize) -> usize, W: Fn(usize) -> bool>(
+    wave: &[Complex<T>],
+    i: usize,
+    n: usize,
+    boundary: u32,
+    index: F,
+    wall: W,
+) -> Complex<T> {
+    let value = |i: Option<usize>| match i.map(&index) {
+        Some(i) if !wall(i) => wave[i],
+        _ => Complex::ZERO,
+    };
+
+    let psi_now = wave[index(i)];
+    let psi_u = value(boundary::upper(i, n, boundary));
+    let psi_l = value(boundary::lower(i, n, boundary));
+
+    psi_u - psi_now * T::from_f32(2.0) + psi_l
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        states::{GaussianPacket, InitialState},
+        testing::{self, TestSimulation},
+        Boundary, Integrator, Solver,
+    };
+
+    #[test]
+    fn keeps_the_norm() {
+        for boundary in [Boundary::Neumann, Boundary::Dirichlet, Boundary::Periodic] {
+            let simulation = TestSimulation::with_integrator(Integrator::CrankNicolson);
+            let simulation = TestSimulation {
+                settings: simulation.settings.with_boundaries(boundary, boundary),
+                psi_0: |x, y| GaussianPacket::new(0.0, 0.0, 6.0, 0.5, 0.0).psi(x, y),
+                potential: |x, _, _| 1e-3 * x * x,
+            };
+            let mut solver = Solver::<f64>::with_precision(&simulation).unwrap();
+            let initial = solver.grid().norm(solver.psi());
+
+            // The ADI factors only conserve the norm exactly when H_x and H_y commute, which the
+            // potential breaks
+            solver.step(200).unwrap();
+            let drift = testing::norm_drift(solver.grid(), solver.psi(), initial);
+            assert!(drift < 1e-5, "{:?} norm drifted by {:e}", boundary, drift);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#[derive(Clone, Copy)]
+pub(crate) enum CurrentWave {
+    Wave1,
+    Wave2,
+    Wave3,
+}
+
+impl CurrentWave {
+    pub fn next(self) -> Self {
+        match self {
+            CurrentWave::Wave1 => CurrentWave::Wave2,
+            CurrentWave::Wave2 => CurrentWave::Wave3,
+            CurrentWave::Wave3 => CurrentWave::Wave1,
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use crate::{
+    boundary, settings::Settings, Complex, Diagnostics, DriftAction, Error, Grid, Real,
+    SolverBackend,
+};
+
+// The norm and the expectation value of the Hamiltonian after `steps` steps, the energy in the
+// simulation's units
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Measurement<T = f32> {
+    steps: usize,
+    time: f64,
+    norm: T,
+    energy: T,
+}
+
+// A conserved quantity the diagnostics watch
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Quantity {
+    Norm,
+    Energy,
+}
+
+// Takes a measurement every `Diagnostics::interval` steps and compares it against the first one
+pub struct Monitor<T = f32> {
+    diagnostics: Diagnostics,
+    initial: Measurement<T>,
+    latest: Measurement<T>,
+}
+
+impl<T: Real> Measurement<T> {
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn norm(&self) -> T {
+        self.norm
+    }
+
+    pub fn energy(&self) -> T {
+        self.energy
+    }
+}
+
+impl<T: Real> Monitor<T> {
+    pub(crate) fn new(diagnostics: Diagnostics, initial: Measurement<T>) -> Self {
+        let monitor = Monitor {
+            diagnostics,
+            initial,
+            latest: initial,
+        };
+        monitor.log();
+        monitor
+    }
+
+    pub fn diagnostics(&self) -> Diagnostics {
+        self.diagnostics
+    }
+
+    pub fn initial(&self) -> Measurement<T> {
+        self.initial
+    }
+
+    pub fn latest(&self) -> Measurement<T> {
+        self.latest
+    }
+
+    pub fn norm_drift(&self) -> T {
+        drift(self.initial.norm, self.latest.norm)
+    }
+
+    pub fn energy_drift(&self) -> T {
+        drift(self.initial.energy, self.latest.energy)
+    }
+
+    // Measures the backend if a measurement is due, returning an error if the settings ask to abort
+    // on a drift
+    pub(crate) fn update<C, B: SolverBackend<C, T> + ?Sized>(
+        &mut self,
+        backend: &mut B,
+        context: &mut C,
+    ) -> Result<(), Error> {
+        let interval = self.diagnostics.interval();
+        if interval == 0 || !backend.steps().is_multiple_of(interval) {
+            return Ok(());
+        }
+
+        self.latest = backend.measure(context);
+        self.log();
+        self.check(
+            Quantity::Norm,
+            self.norm_drift(),
+            self.diagnostics.norm_tolerance(),
+        )?;
+        self.check(
+            Quantity::Energy,
+            self.energy_drift(),
+            self.diagnostics.energy_tolerance(),
+        )
+    }
+
+    // Starts comparing against a new initial measurement, after psi or the potential was replaced
+    pub(crate) fn restart(&mut self, initial: Measurement<T>) {
+        self.initial = initial;
+        self.latest = initial;
+        self.log();
+    }
+
+    fn log(&self) {
+        if self.diagnostics.logging() {
+            println!(
+                "{} steps, t = {:e}: norm = {:e}, energy = {:e}",
+                self.latest.steps,
+                self.latest.time,
+                self.latest.norm.to_f64(),
+                self.latest.energy.to_f64()
+            );
+        }
+    }
+
+    fn check(&self, quantity: Quantity, drift: T, tolerance: Option<f32>) -> Result<(), Error> {
+        let tolerance = match tolerance {
+            Some(tolerance) => tolerance,
+            None => return Ok(()),
+        };
+
+        // A NaN drift never compares as within tolerance
+        if drift.to_f64() <= tolerance as f64 {
+            return Ok(());
+        }
+
+        let error = Error::Drift {
+            steps: self.latest.steps,
+            quantity,
+        };
+        match self.diagnostics.action() {
+            DriftAction::Warn => {
+                eprintln!("warning: {}", error);
+                Ok(())
+            }
+            DriftAction::Abort => Err(error),
+        }
+    }
+}
+
+// <psi|H|psi> is summed with the same discrete Laplacian and boundaries the integrators use, in
+// double precision. `potential` is in the simulation's units and the energy is returned in them.
+pub(crate) fn measure<T: Real>(
+    grid: &Grid,
+    settings: &Settings<T>,
+    psi: &[Complex<T>],
+    potential: &[T],
+    steps: usize,
+    time: f64,
+) -> Measurement<T> {
+    let num_points_x = settings.num_points_x as usize;
+    let num_points_y = settings.num_points_y as usize;
+
+    // h_bar^2 / 2m in hartree bohr^2, the ratio of DTH_2MI and DT_HI
+    let kinetic = (settings.dth_2mi / settings.dt_hi).re.to_f64();
+    let potential_scale = settings.potential_scale.to_f64();
+    let dx2 = settings.dx2.to_f64();
+    let dy2 = settings.dy2.to_f64();
+
+    let mut sum = 0.0;
+    for y in 0..num_points_y {
+        for x in 0..num_points_x {
+            let idx = x + y * num_points_x;
+            let psi_at = |x: Option<usize>, y: Option<usize>| match (x, y) {
+                (Some(x), Some(y)) => psi[x + y * num_points_x].cast::<f64>(),
+                _ => Complex::ZERO,
+            };
+
+            let psi_now = psi[idx].cast::<f64>();
+            let psi_x_u = psi_at(
+                boundary::upper(x, num_points_x, settings.boundary_x),
+                Some(y),
+            );
+            let psi_x_l = psi_at(
+                boundary::lower(x, num_points_x, settings.boundary_x),
+                Some(y),
+            );
+            let psi_y_u = psi_at(
+                Some(x),
+                boundary::upper(y, num_points_y, settings.boundary_y),
+            );
+            let psi_y_l = psi_at(
+                Some(x),
+                boundary::lower(y, num_points_y, settings.boundary_y),
+            );
+
+            let laplacian = (psi_x_u - psi_now * 2.0 + psi_x_l) / dx2
+                + (psi_y_u - psi_now * 2.0 + psi_y_l) / dy2;
+            let h_psi = psi_now * (potential_scale * potential[idx].to_f64()) - laplacian * kinetic;
+
+            sum += (psi_now.conj() * h_psi).re;
+        }
+    }
+
+    let norm = grid.norm(psi);
+    let energy = if norm > T::ZERO {
+        sum * grid.dx() as f64 * grid.dy() as f64 / norm.to_f64() / potential_scale
+    } else {
+        0.0
+    };
+
+    Measurement {
+        steps,
+        time,
+        norm,
+        energy: T::from_f64(energy),
+    }
+}
+
+// Relative to the initial value, or absolute if that is zero
+pub(crate) fn drift<T: Real>(initial: T, value: T) -> T {
+    let difference = (value - initial).abs();
+    if initial == T::ZERO {
+        difference
+    } else {
+        difference / initial.abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        states::{HermiteGauss, InitialState},
+        testing::TestSimulation,
+        Diagnostics, DriftAction, Error, Integrator, Quantity, SimulationSettings, Solver,
+        UnitSystem,
+    };
+
+    // The ground state of V = r^2 / 2, with E = h_bar * omega = 1
+    fn trap(integrator: Integrator, diagnostics: Diagnostics) -> TestSimulation {
+        TestSimulation {
+            settings: SimulationSettings::new(64, 64, 0.25, 0.25, 0.005, 1.0)
+                .with_units(UnitSystem::Dimensionless)
+                .with_integrator(integrator)
+                .with_diagnostics(diagnostics),
+            psi_0: |x, y| HermiteGauss::new(0, 0, 1.0).psi(x, y),
+            potential: |x, y, _| 0.5 * (x * x + y * y),
+        }
+    }
+
+    #[test]
+    fn measures_the_trap_energy() {
+        let diagnostics = Diagnostics::new(10).with_energy_tolerance(1e-4);
+        let mut solver =
+            Solver::<f64>::with_precision(&trap(Integrator::Leapfrog, diagnostics)).unwrap();
+
+        let measurement = solver.measure();
+        assert!((measurement.norm() - 1.0).abs() < 1e-6);
+        assert!(
+            (measurement.energy() - 1.0).abs() < 1e-2,
+            "energy {}",
+            measurement.energy()
+        );
+
+        solver.step(100).unwrap();
+        let monitor = solver.monitor().unwrap();
+        assert_eq!(monitor.latest().steps(), 100);
+        assert!(monitor.energy_drift() < 1e-4);
+    }
+
+    #[test]
+    fn aborts_on_drift() {
+        let diagnostics = Diagnostics::new(10)
+            .with_norm_tolerance(1e-3)
+            .with_action(DriftAction::Abort);
+        let mut solver =
+            Solver::<f64>::with_precision(&trap(Integrator::ForwardEuler, diagnostics)).unwrap();
+
+        match solver.step(10_000) {
+            Err(Error::Drift { steps, quantity }) => {
+                assert_eq!(quantity, Quantity::Norm);
+                assert_eq!(steps, solver.steps());
+                assert!(steps < 10_000);
+            }
+            result => panic!("expected a drift error, got {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use crate::{boundary, settings::Settings, Complex, Eigenstate, Error, Grid, Simulation};
+
+// Finds the lowest eigenpairs of the discrete Hamiltonian the integrators step with: the 5-point
+// Laplacian with the simulation's boundaries plus the potential at t = 0, hard walls included. It
+// runs Chebyshev filtered subspace iteration on the CPU in double precision, which needs nothing
+// but products with the Hamiltonian and, as a block method, resolves degenerate levels.
+pub struct Eigensolver {
+    grid: Grid,
+    num_points_x: usize,
+    num_points_y: usize,
+    boundary_x: u32,
+    boundary_y: u32,
+    kinetic_x: f64,
+    kinetic_y: f64,
+    potential: Vec<f64>,
+    walls: Vec<bool>,
+    potential_scale: f64,
+    upper_bound: f64,
+    tolerance: f64,
+    max_iterations: usize,
+}
+
+// Degree of the Chebyshev polynomial applied every iteration
+const DEGREE: usize = 16;
+
+impl Eigensolver {
+    pub fn new<S: Simulation>(simulation: &S) -> Result<Self, Error> {
+        let simulation_settings = simulation.simulation_settings();
+        let grid = Grid::new(&simulation_settings)?;
+        let settings = Settings::<f64>::new(&simulation_settings);
+
+        // h_bar^2 / 2m in hartree bohr^2, the ratio of DTH_2MI and DT_HI
+        let kinetic = (settings.dth_2mi / settings.dt_hi).re;
+        let kinetic_x = kinetic / settings.dx2;
+        let kinetic_y = kinetic / settings.dy2;
+
+        let potential: Vec<f64> = grid.sample(|x, y| {
+            simulation.time_dependent_potential(x, y, 0.0) as f64 * settings.potential_scale
+        });
+        let walls: Vec<bool> = potential.iter().map(|v| !v.is_finite()).collect();
+
+        // Gershgorin bound on the largest eigenvalue
+        let largest_potential = potential
+            .iter()
+            .filter(|v| v.is_finite())
+            .fold(f64::MIN, |a, b| a.max(*b));
+        let upper_bound = largest_potential + 4.0 * (kinetic_x + kinetic_y);
+
+        Ok(Eigensolver {
+            grid,
+            num_points_x: settings.num_points_x as usize,
+            num_points_y: settings.num_points_y as usize,
+            boundary_x: settings.boundary_x,
+            boundary_y: settings.boundary_y,
+            kinetic_x,
+            kinetic_y,
+            potential,
+            walls,
+            potential_scale: settings.potential_scale,
+            upper_bound,
+            tolerance: 1e-8,
+            max_iterations: 1000,
+        })
+    }
+
+    // Largest residual |H psi - E psi| accepted, relative to the width of the spectrum
+    pub fn with_tolerance(self, tolerance: f64) -> Self {
+        Eigensolver { tolerance, ..self }
+    }
+
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self {
+        Eigensolver {
+            max_iterations,
+            ..self
+        }
+    }
+
+    // The lowest `k` eigenstates from the lowest up, fewer if the grid has fewer points outside of
+    // hard walls
+    pub fn solve(&self, k: usize) -> Result<Vec<Eigenstate<f64>>, Error> {
+        let free_points = self.walls.iter().filter(|wall| !**wall).count();
+        let k = k.min(free_points);
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+
+        // A few more vectors than asked for speed up convergence of the highest wanted state
+        let block_size = (k + (k / 4).max(4)).min(free_points);
+
+        let mut vectors = self.start_vectors(block_size);
+        orthonormalize(&mut vectors);
+        let (mut values, _) = self.rayleigh_ritz(&mut vectors);
+
+        let scale = self.upper_bound - values[0];
+        for iteration in 1..=self.max_iterations {
+            vectors = self.filter(&vectors, values[block_size - 1], values[0]);
+            orthonormalize(&mut vectors);
+            let products;
+            (values, products) = self.rayleigh_ritz(&mut vectors);
+
+            let converged = (0..k).all(|i| {
+                let residual: f64 = products[i]
+                    .iter()
+                    .zip(&vectors[i])
+                    .map(|(h_psi, psi)| (h_psi - values[i] * psi).powi(2))
+                    .sum();
+                residual.sqrt() <= self.tolerance * scale
+            });
+
+            if converged {
+                return Ok(vectors
+                    .into_iter()
+                    .zip(values)
+                    .take(k)
+                    .map(|(vector, value)| self.eigenstate(vector, value, iteration))
+                    .collect());
+            }
+        }
+
+        Err(Error::NotConverged {
+            steps: self.max_iterations,
+        })
+    }
+
+    // H psi in hartrees
+    fn apply(&self, psi: &[f64], out: &mut [f64]) {
+        let num_points_x = self.num_points_x;
+        let num_points_y = self.num_points_y;
+
+        for y in 0..num_points_y {
+            for x in 0..num_points_x {
+                let idx = x + y * num_points_x;
+                if self.walls[idx] {
+                    out[idx] = 0.0;
+                    continue;
+                }
+
+                let psi_at = |x: Option<usize>, y: Option<usize>| match (x, y) {
+                    (Some(x), Some(y)) => psi[x + y * num_points_x],
+                    _ => 0.0,
+                };
+
+                let psi_now = psi[idx];
+                let psi_x_u = psi_at(boundary::upper(x, num_points_x, self.boundary_x), Some(y));
+                let psi_x_l = psi_at(boundary::lower(x, num_points_x, self.boundary_x), Some(y));
+                let psi_y_u = psi_at(Some(x), boundary::upper(y, num_points_y, self.boundary_y));
+                let psi_y_l = psi_at(Some(x), boundary::lower(y, num_points_y, self.boundary_y));
+
+                out[idx] = self.potential[idx] * psi_now
+                    - self.kinetic_x * (psi_x_u - 2.0 * psi_now + psi_x_l)
+                    - self.kinetic_y * (psi_y_u - 2.0 * psi_now + psi_y_l);
+            }
+        }
+    }
+
+    // Pseudo-random vectors, so no symmetry of the Hamiltonian hides a state from the block
+    fn start_vectors(&self, count: usize) -> Vec<Vec<f64>> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        (0..count)
+            .map(|_| {
+                self.walls
+                    .iter()
+                    .map(|wall| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        if *wall {
+                            0.0
+                        } else {
+                            (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Applies the Chebyshev polynomial that is small on [cutoff, upper_bound] and grows quickly
+    // below it, scaled to stay near one at `lowest`
+    fn filter(&self, vectors: &[Vec<f64>], cutoff: f64, lowest: f64) -> Vec<Vec<f64>> {
+        // Ritz values can reach the Gershgorin bound, or pass it by rounding, once the block spans
+        // the top of the spectrum. The interval is kept open so the scaling below stays finite.
+        let upper_bound = self
+            .upper_bound
+            .max(cutoff + 1e-3 * (self.kinetic_x + self.kinetic_y));
+        let half_width = (upper_bound - cutoff) / 2.0;
+        let center = (upper_bound + cutoff) / 2.0;
+        let sigma = half_width / (lowest - center);
+        let tau = 2.0 / sigma;
+
+        let n = self.potential.len();
+        let mut product = vec![0.0; n];
+
+        vectors
+            .iter()
+            .map(|vector| {
+                let mut previous = vector.clone();
+                self.apply(&previous, &mut product);
+                let mut current: Vec<f64> = product
+                    .iter()
+                    .zip(&previous)
+                    .map(|(h_psi, psi)| (h_psi - center * psi) * sigma / half_width)
+                    .collect();
+
+                let mut sigma_previous = sigma;
+                for _ in 1..DEGREE {
+                    let sigma_next = 1.0 / (tau - sigma_previous);
+                    self.apply(&current, &mut product);
+                    for i in 0..n {
+                        let next = (product[i] - center * current[i]) * 2.0 * sigma_next
+                            / half_width
+                            - sigma_previous * sigma_next * previous[i];
+                        previous[i] = current[i];
+                        current[i] = next;
+                    }
+                    sigma_previous = sigma_next;
+                }
+                current
+            })
+            .collect()
+    }
+
+    // Diagonalizes H within the span of `vectors`, rotating them into its eigenvectors in order of
+    // increasing eigenvalue. Returns the eigenvalues and H applied to each vector.
+    fn rayleigh_ritz(&self, vectors: &mut [Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+        let n = self.potential.len();
+        let products: Vec<Vec<f64>> = vectors
+            .iter()
+            .map(|vector| {
+                let mut product = vec![0.0; n];
+                self.apply(vector, &mut product);
+                product
+            })
+            .collect();
+
+        let size = vectors.len();
+        let mut matrix = vec![vec![0.0; size]; size];
+        for i in 0..size {
+            for j in i..size {
+                let value = dot(&vectors[i], &products[j]);
+                matrix[i][j] = value;
+                matrix[j][i] = value;
+            }
+        }
+
+        let (values, rotation) = jacobi(matrix);
+        let rotate = |basis: &[Vec<f64>]| -> Vec<Vec<f64>> {
+            (0..size)
+                .map(|j| {
+                    let mut out = vec![0.0; n];
+                    for (i, vector) in basis.iter().enumerate() {
+                        let coefficient = rotation[i][j];
+                        for (out, value) in out.iter_mut().zip(vector) {
+                            *out += coefficient * value;
+                        }
+                    }
+                    out
+                })
+                .collect()
+        };
+
+        let rotated = rotate(vectors);
+        vectors.clone_from_slice(&rotated);
+        (values, rotate(&products))
+    }
+
+    // Scales a unit vector into a wave function normalized over the grid
+    fn eigenstate(&self, vector: Vec<f64>, value: f64, iterations: usize) -> Eigenstate<f64> {
+        let scale = 1.0 / (self.grid.dx() as f64 * self.grid.dy() as f64).sqrt();
+        let psi = vector
+            .into_iter()
+            .map(|value| Complex::from(value * scale))
+            .collect();
+
+        Eigenstate::new(
+            self.grid.clone(),
+            psi,
+            value / self.potential_scale,
+            iterations,
+        )
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+// Modified Gram-Schmidt, twice to hold orthogonality in floating point
+fn orthonormalize(vectors: &mut [Vec<f64>]) {
+    for j in 0..vectors.len() {
+        for _ in 0..2 {
+            for i in 0..j {
+                let projection = dot(&vectors[i], &vectors[j]);
+                let (before, after) = vectors.split_at_mut(j);
+                for (value, basis) in after[0].iter_mut().zip(&before[i]) {
+                    *value -= projection * basis;
+                }
+            }
+        }
+
+        let norm = dot(&vectors[j], &vectors[j]).sqrt();
+        for value in vectors[j].iter_mut() {
+            *value /= norm;
+        }
+    }
+}
+
+// Cyclic Jacobi eigenvalue algorithm for a small symmetric matrix. Returns the eigenvalues in
+// increasing order and the matching eigenvectors as columns.
+fn jacobi(mut matrix: Vec<Vec<f64>>) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let size = matrix.len();
+    let mut vectors = vec![vec![0.0; size]; size];
+    for (i, row) in vectors.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+
+    for _ in 0..100 {
+        let off_diagonal: f64 = (0..size)
+            .flat_map(|i| (0..size).filter(move |j| *j != i).map(move |j| (i, j)))
+            .map(|(i, j)| matrix[i][j] * matrix[i][j])
+            .sum();
+        let diagonal: f64 = (0..size).map(|i| matrix[i][i] * matrix[i][i]).sum();
+        if off_diagonal <= f64::EPSILON * f64::EPSILON * diagonal {
+            break;
+        }
+
+        for p in 0..size {
+            for q in p + 1..size {
+                if matrix[p][q] == 0.0 {
+                    continue;
+                }
+
+                let theta = (matrix[q][q] - matrix[p][p]) / (2.0 * matrix[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for row in matrix.iter_mut() {
+                    let (a, b) = (row[p], row[q]);
+                    row[p] = c * a - s * b;
+                    row[q] = s * a + c * b;
+                }
+                let (upper, lower) = matrix.split_at_mut(q);
+                for (a, b) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    (*a, *b) = (c * *a - s * *b, s * *a + c * *b);
+                }
+                for row in vectors.iter_mut() {
+                    let (a, b) = (row[p], row[q]);
+                    row[p] = c * a - s * b;
+                    row[q] = s * a + c * b;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..size).collect();
+    order.sort_by(|a, b| matrix[*a][*a].total_cmp(&matrix[*b][*b]));
+
+    let values = order.iter().map(|i| matrix[*i][*i]).collect();
+    let vectors = vectors
+        .iter()
+        .map(|row| order.iter().map(|i| row[*i]).collect())
+        .collect();
+    (values, vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::TestSimulation, Boundary, ImaginaryTime, Simulation, SimulationSettings,
+        UnitSystem,
+    };
+
+    #[test]
+    fn finds_the_trap_levels() {
+        let states = Eigensolver::new(&TestSimulation::trap())
+            .unwrap()
+            .solve(6)
+            .unwrap();
+
+        // E = nx + ny + 1, up to the grid's discretization error
+        for (state, expected) in states.iter().zip([1.0, 2.0, 2.0, 3.0, 3.0, 3.0]) {
+            let energy = state.energy();
+            assert!(
+                (energy - expected).abs() < 2e-2 * expected,
+                "{} != {}",
+                energy,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn agrees_with_imaginary_time() {
+        let simulation = TestSimulation::trap();
+        let states = Eigensolver::new(&simulation).unwrap().solve(1).unwrap();
+        let mut imaginary_time = ImaginaryTime::<f64>::with_precision(&simulation).unwrap();
+        let ground_state = imaginary_time.find(1e-10, 200_000).unwrap();
+
+        // Imaginary time relaxes to the ground state of its split step, which differs at O(d_tau)
+        let difference = (states[0].energy() - ground_state.energy()).abs();
+        assert!(difference < 1e-4, "energies differ by {:e}", difference);
+    }
+
+    #[test]
+    fn solves_a_block_that_reaches_the_upper_bound() {
+        // The highest level of a periodic 2 x 2 grid is exactly the Gershgorin bound, so a block
+        // holding all four points can close the interval the filter damps
+        let simulation = TestSimulation {
+            settings: SimulationSettings::new(2, 2, 1.0, 1.0, 0.05, 1.0)
+                .with_units(UnitSystem::Dimensionless)
+                .with_boundaries(Boundary::Periodic, Boundary::Periodic),
+            ..TestSimulation::new()
+        };
+        let eigensolver = Eigensolver::new(&simulation).unwrap();
+        let vectors = eigensolver.start_vectors(4);
+        let filtered = eigensolver.filter(&vectors, eigensolver.upper_bound, 0.0);
+        assert!(filtered.iter().flatten().all(|value| value.is_finite()));
+
+        let states = eigensolver.solve(4).unwrap();
+        for (state, expected) in states.iter().zip([0.0, 2.0, 2.0, 4.0]) {
+            let energy = state.energy();
+            assert!(
+                (energy - expected).abs() < 1e-8,
+                "{} != {}",
+                energy,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{Backend, Integrator, Quantity};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error {
+    InvalidGridSize {
+        num_points_x: usize,
+        num_points_y: usize,
+    },
+    UnsupportedIntegrator {
+        integrator: Integrator,
+        backend: Backend,
+    },
+    UnsupportedWalls {
+        integrator: Integrator,
+    },
+    Drift {
+        steps: usize,
+        quantity: Quantity,
+    },
+    Recording {
+        path: &'static str,
+        kind: std::io::ErrorKind,
+    },
+    NotConverged {
+        steps: usize,
+    },
+    Io {
+        kind: std::io::ErrorKind,
+    },
+    SizeMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    InvalidCheckpoint,
+    IncompatibleCheckpoint,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidGridSize {
+                num_points_x,
+                num_points_y,
+            } => write!(f, "invalid grid size {}x{}", num_points_x, num_points_y),
+            Error::UnsupportedIntegrator {
+                integrator,
+                backend,
+            } => write!(
+                f,
+                "{:?} is not available on the {:?} backend",
+                integrator, backend
+            ),
+            Error::UnsupportedWalls { integrator } => {
+                write!(f, "{:?} can't hold psi at zero on hard walls", integrator)
+            }
+            Error::Drift { steps, quantity } => write!(
+                f,
+                "{:?} drifted past its tolerance after {} steps",
+                quantity, steps
+            ),
+            Error::Recording { path, kind } => write!(f, "unable to record to {}: {}", path, kind),
+            Error::NotConverged { steps } => write!(f, "not converged after {} steps", steps),
+            Error::Io { kind } => write!(f, "{}", kind),
+            Error::SizeMismatch { expected, actual } => write!(
+                f,
+                "expected {} values, one per grid point, but got {}",
+                expected, actual
+            ),
+            Error::InvalidCheckpoint => write!(f, "not a checkpoint file or an unknown version"),
+            Error::IncompatibleCheckpoint => {
+                write!(f, "checkpoint was taken with different settings")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
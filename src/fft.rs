@@ -0,0 +1,144 @@
+use crate::{Complex, Real};
+use std::f64::consts::PI;
+
+// A precomputed discrete Fourier transform of a fixed length. Powers of two use an iterative
+// radix-2 transform, other lengths go through Bluestein's algorithm on a padded radix-2 transform.
+pub(crate) struct Fft<T> {
+    n: usize,
+    twiddles: Vec<Complex<T>>,
+    bluestein: Option<Box<Bluestein<T>>>,
+}
+
+struct Bluestein<T> {
+    fft: Fft<T>,
+    chirp: Vec<Complex<T>>,
+    // Transform of the conjugate chirp, zero padded to the length of `fft`
+    filter: Vec<Complex<T>>,
+    scratch: Vec<Complex<T>>,
+}
+
+impl<T: Real> Fft<T> {
+    pub fn new(n: usize) -> Self {
+        if n.is_power_of_two() {
+            let twiddles = (0..n / 2)
+                .map(|k| {
+                    let angle = -2.0 * PI * k as f64 / n as f64;
+                    Complex::new(T::from_f64(angle.cos()), T::from_f64(angle.sin()))
+                })
+                .collect();
+
+            return Fft {
+                n,
+                twiddles,
+                bluestein: None,
+            };
+        }
+
+        let m = (2 * n - 1).next_power_of_two();
+        let mut fft = Fft::new(m);
+
+        let chirp: Vec<Complex<T>> = (0..n)
+            .map(|k| {
+                // k^2 mod 2n keeps the angle small for long transforms
+                let k2 = (k * k) % (2 * n);
+                let angle = -PI * k2 as f64 / n as f64;
+                Complex::new(T::from_f64(angle.cos()), T::from_f64(angle.sin()))
+            })
+            .collect();
+
+        let mut filter = vec![Complex::ZERO; m];
+        filter[0] = chirp[0].conj();
+        for k in 1..n {
+            filter[k] = chirp[k].conj();
+            filter[m - k] = chirp[k].conj();
+        }
+        fft.forward(&mut filter);
+
+        Fft {
+            n,
+            twiddles: Vec::new(),
+            bluestein: Some(Box::new(Bluestein {
+                fft,
+                chirp,
+                filter,
+                scratch: vec![Complex::ZERO; m],
+            })),
+        }
+    }
+
+    pub fn forward(&mut self, values: &mut [Complex<T>]) {
+        assert_eq!(values.len(), self.n);
+
+        match &mut self.bluestein {
+            None => radix_2(&self.twiddles, values),
+            Some(bluestein) => bluestein.forward(values),
+        }
+    }
+
+    // Inverse transform including the 1 / n normalization
+    pub fn inverse(&mut self, values: &mut [Complex<T>]) {
+        for value in values.iter_mut() {
+            *value = value.conj();
+        }
+
+        self.forward(values);
+
+        let scale = T::ONE / T::from_usize(self.n);
+        for value in values.iter_mut() {
+            *value = value.conj() * scale;
+        }
+    }
+}
+
+impl<T: Real> Bluestein<T> {
+    fn forward(&mut self, values: &mut [Complex<T>]) {
+        for (k, scratch) in self.scratch.iter_mut().enumerate() {
+            *scratch = if k < values.len() {
+                values[k] * self.chirp[k]
+            } else {
+                Complex::ZERO
+            };
+        }
+
+        self.fft.forward(&mut self.scratch);
+        for (scratch, filter) in self.scratch.iter_mut().zip(&self.filter) {
+            *scratch *= *filter;
+        }
+        self.fft.inverse(&mut self.scratch);
+
+        for ((value, scratch), chirp) in values.iter_mut().zip(&self.scratch).zip(&self.chirp) {
+            *value = *scratch * *chirp;
+        }
+    }
+}
+
+fn radix_2<T: Real>(twiddles: &[Complex<T>], values: &mut [Complex<T>]) {
+    let n = values.len();
+    if n <= 1 {
+        return;
+    }
+
+    // Bit reversal permutation
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= n {
+        let stride = n / length;
+        for start in (0..n).step_by(length) {
+            for k in 0..length / 2 {
+                let even = values[start + k];
+                let odd = values[start + k + length / 2] * twiddles[k * stride];
+
+                values[start + k] = even + odd;
+                values[start + k + length / 2] = even - odd;
+            }
+        }
+        length *= 2;
+    }
+}
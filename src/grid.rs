@@ -0,0 +1,90 @@
+use crate::{Complex, Error, Real, SimulationSettings};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grid {
+    num_points_x: usize,
+    num_points_y: usize,
+    width: f32,
+    height: f32,
+    dx: f32,
+    dy: f32,
+}
+
+impl Grid {
+    pub fn new(settings: &SimulationSettings) -> Result<Self, Error> {
+        if settings.num_points_x() == 0 || settings.num_points_y() == 0 {
+            return Err(Error::InvalidGridSize {
+                num_points_x: settings.num_points_x(),
+                num_points_y: settings.num_points_y(),
+            });
+        }
+
+        Ok(Grid {
+            num_points_x: settings.num_points_x(),
+            num_points_y: settings.num_points_y(),
+            width: ((settings.num_points_x() - 1) as f32) * settings.dx(),
+            height: ((settings.num_points_y() - 1) as f32) * settings.dy(),
+            dx: settings.dx(),
+            dy: settings.dy(),
+        })
+    }
+
+    pub fn num_points_x(&self) -> usize {
+        self.num_points_x
+    }
+
+    pub fn num_points_y(&self) -> usize {
+        self.num_points_y
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    pub fn dx(&self) -> f32 {
+        self.dx
+    }
+
+    pub fn dy(&self) -> f32 {
+        self.dy
+    }
+
+    // Fails unless `len` values cover the grid exactly, one per point
+    pub(crate) fn check_size(&self, len: usize) -> Result<(), Error> {
+        let expected = self.num_points_x * self.num_points_y;
+        if len == expected {
+            Ok(())
+        } else {
+            Err(Error::SizeMismatch {
+                expected,
+                actual: len,
+            })
+        }
+    }
+
+    // Evaluates `f` at every grid point, row by row, with the grid centered on the origin
+    pub fn sample<T, F: Fn(f32, f32) -> T>(&self, f: F) -> Vec<T> {
+        let mut values = Vec::with_capacity(self.num_points_x * self.num_points_y);
+        let base_x = -(self.width / 2.0);
+        let base_y = -(self.height / 2.0);
+        for y in 0..self.num_points_y {
+            for x in 0..self.num_points_x {
+                let x = base_x + x as f32 * self.dx;
+                let y = base_y + y as f32 * self.dy;
+
+                values.push(f(x, y));
+            }
+        }
+        values
+    }
+
+    // The sum of |psi|^2 dx dy over the grid, accumulated in double precision
+    pub fn norm<T: Real>(&self, psi: &[Complex<T>]) -> T {
+        let sum: f64 = psi.iter().map(|psi| psi.norm_sqr().to_f64()).sum();
+        T::from_f64(sum * self.dx as f64 * self.dy as f64)
+    }
+}
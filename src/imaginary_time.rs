@@ -0,0 +1,257 @@
+use crate::{
+    boundary, cpu_simulation_runner::explicit_step, diagnostics, settings::Settings,
+    solver_backend::sample_psi_0, states::InitialState, Complex, Error, Grid, Real, Simulation,
+};
+
+// Relaxes `psi_0` towards the lowest eigenstate of the simulation's Hamiltonian by stepping in
+// imaginary time on the CPU. psi is renormalized every step and kept orthogonal to every state
+// found so far, so each call to `find` returns the next state up. `psi_0` has to overlap the state
+// being looked for, a symmetric guess never finds an antisymmetric state.
+pub struct ImaginaryTime<T: Real = f32> {
+    grid: Grid,
+    settings: Settings<T>,
+    step_settings: Settings<T>,
+    potential: Vec<T>,
+    damping: Vec<T>,
+    guess: Vec<Complex<T>>,
+    states: Vec<Eigenstate<T>>,
+
+    // The potential acts through `damping` rather than through the explicit step
+    no_potential: Vec<T>,
+
+    // Scratch space for a step
+    psi: Vec<Complex<T>>,
+    next: Vec<Complex<T>>,
+    output: Vec<T>,
+}
+
+// A normalized eigenstate on the simulation's grid with its energy in the simulation's units. It is
+// an `InitialState`, taking the value of the nearest grid point, so it can be used as `psi_0`.
+#[derive(Clone)]
+pub struct Eigenstate<T: Real = f32> {
+    grid: Grid,
+    psi: Vec<Complex<T>>,
+    energy: T,
+    steps: usize,
+}
+
+// Steps between checks of the energy for convergence
+const CHECK_INTERVAL: usize = 50;
+
+impl ImaginaryTime {
+    pub fn new<S: Simulation>(simulation: &S) -> Result<Self, Error> {
+        ImaginaryTime::with_precision(simulation)
+    }
+}
+
+impl<T: Real> ImaginaryTime<T> {
+    pub fn with_precision<S: Simulation>(simulation: &S) -> Result<Self, Error> {
+        let simulation_settings = simulation.simulation_settings();
+        let grid = Grid::new(&simulation_settings)?;
+        let num_points = grid.num_points_x() * grid.num_points_y();
+
+        let (guess, _) = sample_psi_0(&grid, simulation);
+
+        let settings = Settings::<T>::new(&simulation_settings);
+        let step_settings = settings.imaginary_time();
+
+        // exp(-d_tau * V / h_bar), with hard walls pinning psi to zero
+        let mut potential =
+            grid.sample(|x, y| T::from_f32(simulation.time_dependent_potential(x, y, 0.0)));
+        let decay: Vec<T> = potential
+            .iter()
+            .map(|v| (step_settings.dt_hi.re * step_settings.potential_scale * *v).exp())
+            .collect();
+        let mut damping = vec![T::ZERO; num_points];
+        boundary::walls(&mut potential, &decay, &mut damping);
+
+        Ok(ImaginaryTime {
+            grid,
+            settings,
+            step_settings,
+            potential,
+            damping,
+            psi: guess.clone(),
+            guess,
+            states: Vec::new(),
+            no_potential: vec![T::ZERO; num_points],
+            next: vec![Complex::ZERO; num_points],
+            output: vec![T::ZERO; num_points],
+        })
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    // Every state found so far, from the lowest up
+    pub fn states(&self) -> &[Eigenstate<T>] {
+        &self.states
+    }
+
+    // Relaxes until the energy changes by no more than `tolerance` relative to itself over 50
+    // steps, giving up with `Error::NotConverged` after `max_steps`
+    pub fn find(&mut self, tolerance: f64, max_steps: usize) -> Result<&Eigenstate<T>, Error> {
+        self.psi.copy_from_slice(&self.guess);
+        if !self.project() {
+            return Err(Error::NotConverged { steps: 0 });
+        }
+
+        let mut energy = self.energy();
+        let mut steps = 0;
+        while steps < max_steps {
+            explicit_step(
+                &self.step_settings,
+                &self.psi,
+                &self.psi,
+                &mut self.next,
+                &self.no_potential,
+                &self.damping,
+                &mut self.output,
+            );
+            std::mem::swap(&mut self.psi, &mut self.next);
+            steps += 1;
+
+            if !self.project() {
+                return Err(Error::NotConverged { steps });
+            }
+
+            if steps.is_multiple_of(CHECK_INTERVAL) {
+                let previous = energy;
+                energy = self.energy();
+                if diagnostics::drift(previous, energy) <= tolerance {
+                    self.states.push(Eigenstate::new(
+                        self.grid.clone(),
+                        self.psi.clone(),
+                        T::from_f64(energy),
+                        steps,
+                    ));
+                    return Ok(self.states.last().unwrap());
+                }
+            }
+        }
+
+        Err(Error::NotConverged { steps })
+    }
+
+    // Gram-Schmidt against the states found so far and then renormalization. Returns false if
+    // nothing is left of psi.
+    fn project(&mut self) -> bool {
+        let area = T::from_f32(self.grid.dx() * self.grid.dy());
+        for state in &self.states {
+            let overlap: Complex<T> = state
+                .psi
+                .iter()
+                .zip(&self.psi)
+                .map(|(phi, psi)| phi.conj() * *psi)
+                .sum::<Complex<T>>()
+                * area;
+
+            for (psi, phi) in self.psi.iter_mut().zip(&state.psi) {
+                *psi -= overlap * *phi;
+            }
+        }
+
+        let norm = self.grid.norm(&self.psi);
+        if !norm.is_finite() || norm <= T::ZERO {
+            return false;
+        }
+
+        let scale = T::ONE / norm.sqrt();
+        for psi in self.psi.iter_mut() {
+            *psi *= scale;
+        }
+        true
+    }
+
+    fn energy(&self) -> f64 {
+        diagnostics::measure(
+            &self.grid,
+            &self.settings,
+            &self.psi,
+            &self.potential,
+            0,
+            0.0,
+        )
+        .energy()
+        .to_f64()
+    }
+}
+
+impl<T: Real> Eigenstate<T> {
+    pub(crate) fn new(grid: Grid, psi: Vec<Complex<T>>, energy: T, steps: usize) -> Self {
+        Eigenstate {
+            grid,
+            psi,
+            energy,
+            steps,
+        }
+    }
+
+    // psi at every grid point, row by row
+    pub fn values(&self) -> &[Complex<T>] {
+        &self.psi
+    }
+
+    pub fn energy(&self) -> T {
+        self.energy
+    }
+
+    // Iterations it took to converge, imaginary time steps or `Eigensolver` filter passes
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+}
+
+impl<T: Real> InitialState for Eigenstate<T> {
+    fn psi(&self, x: f32, y: f32) -> Complex {
+        let nearest = |position: f32, extent: f32, d: f32, n: usize| {
+            let i = ((position + extent / 2.0) / d).round();
+            if i >= 0.0 && (i as usize) < n {
+                Some(i as usize)
+            } else {
+                None
+            }
+        };
+
+        let x = nearest(
+            x,
+            self.grid.width(),
+            self.grid.dx(),
+            self.grid.num_points_x(),
+        );
+        let y = nearest(
+            y,
+            self.grid.height(),
+            self.grid.dy(),
+            self.grid.num_points_y(),
+        );
+        match (x, y) {
+            (Some(x), Some(y)) => self.psi[x + y * self.grid.num_points_x()].cast(),
+            _ => Complex::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestSimulation;
+
+    #[test]
+    fn finds_the_trap_levels() {
+        let mut imaginary_time =
+            ImaginaryTime::<f64>::with_precision(&TestSimulation::trap()).unwrap();
+
+        // The levels of the grid's discrete Hamiltonian sit slightly below n + 1
+        for expected in [1.0, 2.0, 2.0] {
+            let energy = imaginary_time.find(1e-9, 200_000).unwrap().energy();
+            assert!(
+                (energy - expected).abs() < 2e-2,
+                "{} != {}",
+                energy,
+                expected
+            );
+        }
+    }
+}
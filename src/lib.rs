@@ -1,22 +1,73 @@
+use cpu_simulation_runner::CpuSimulationRunner;
 use observer::Observer;
+use recorder::Recorder;
 use renderer::Renderer;
 use simulation_runner::SimulationRunner;
-use std::marker::PhantomData;
+use solver_backend::update_potential;
 
+mod boundary;
+mod checkpoint;
+mod complex;
+mod cpu_simulation_runner;
+mod crank_nicolson;
+mod current_wave;
+mod diagnostics;
+mod eigensolver;
+mod error;
+mod fft;
+mod grid;
+mod imaginary_time;
+mod observables;
 mod observer;
+mod real;
+mod recorder;
 mod renderer;
+mod resample;
+mod settings;
 mod simulation;
 mod simulation_runner;
+mod snapshot;
+mod solver;
+mod solver_backend;
+mod split_step;
+#[cfg(test)]
+mod testing;
 
-pub use simulation::{RenderSettings, Simulation, SimulationSettings};
+pub mod potentials;
+pub mod states;
+
+pub use checkpoint::Checkpoint;
+pub use complex::Complex;
+pub use diagnostics::{Measurement, Monitor, Quantity};
+pub use eigensolver::Eigensolver;
+pub use error::Error;
+pub use grid::Grid;
+pub use imaginary_time::{Eigenstate, ImaginaryTime};
+pub use observables::Observables;
+pub use real::Real;
+pub use simulation::{
+    AbsorbingLayer, Backend, Boundary, Checkpointing, Diagnostics, DriftAction, Integrator,
+    RecordFormat, Recording, RenderSettings, Resampling, Simulation, SimulationSettings, Snapshots,
+    UnitSystem,
+};
+pub use solver::Solver;
+pub use solver_backend::{Density, SolverBackend};
+
+type Context = colosseum::Window<alexandria::StateTrackingInput>;
 
 struct Game<S: Simulation> {
     observer: Observer,
-    simulation_runner: SimulationRunner,
+    simulation_runner: Box<dyn SolverBackend<Context>>,
     renderer: Renderer,
     tick_time: f32,
     time_scale: f32,
-    phantom: PhantomData<S>,
+    potential_update_interval: Option<usize>,
+    monitor: Option<Monitor>,
+    recorder: Option<Recorder>,
+    checkpointing: Option<Checkpointing>,
+    snapshots: Option<Snapshots>,
+    halted: bool,
+    simulation: S,
 }
 
 pub fn run<S: Simulation>() -> ! {
@@ -29,8 +80,70 @@ impl<S: Simulation> colosseum::Game for Game<S> {
     fn new(window: &mut colosseum::Window<Self::Input>) -> Self {
         let simulation = S::new();
 
-        let simulation_runner = SimulationRunner::new(&simulation, window);
-        let renderer = Renderer::new(&simulation_runner, &simulation, window);
+        // There is no caller to return an error to, so invalid settings end the program
+        let simulation_runner: Result<Box<dyn SolverBackend<Context>>, Error> =
+            match simulation.simulation_settings().backend() {
+                Backend::Gpu => SimulationRunner::new(&simulation, window)
+                    .map(|runner| Box::new(runner) as Box<dyn SolverBackend<Context>>),
+                Backend::Cpu => CpuSimulationRunner::new(&simulation)
+                    .map(|runner| Box::new(runner) as Box<dyn SolverBackend<Context>>),
+            };
+        let mut simulation_runner = simulation_runner.unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1)
+        });
+        let checkpointing = simulation.simulation_settings().checkpointing();
+        // A checkpoint that can't be resumed from is reported and the run starts over instead
+        let resumed = checkpointing.is_some_and(|checkpointing| {
+            checkpoint::resume(
+                checkpointing,
+                &simulation,
+                simulation_runner.as_mut(),
+                window,
+            )
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                false
+            })
+        });
+        // Rescaling hides how far psi_0 was from unit norm, so it's reported with the other output
+        let settings = simulation.simulation_settings();
+        let logging = settings
+            .diagnostics()
+            .is_some_and(|diagnostics| diagnostics.logging());
+        if settings.normalize() && (logging || settings.recording().is_some()) {
+            println!(
+                "psi_0 had norm {:e} and was rescaled to 1",
+                simulation_runner.initial_norm()
+            );
+        }
+        let monitor = simulation
+            .simulation_settings()
+            .diagnostics()
+            .map(|diagnostics| Monitor::new(diagnostics, simulation_runner.measure(window)));
+        // Output that can't be written is reported and left off, as it is while running
+        let recorder = simulation
+            .simulation_settings()
+            .recording()
+            .and_then(|recording| {
+                Recorder::new(recording, resumed, simulation_runner.as_mut(), window)
+                    .map_err(|error| eprintln!("{}", error))
+                    .ok()
+            });
+        let snapshots = simulation
+            .simulation_settings()
+            .snapshots()
+            .filter(|snapshots| {
+                snapshot::write(
+                    snapshots.directory(),
+                    simulation.simulation_settings().units(),
+                    simulation_runner.as_mut(),
+                    window,
+                )
+                .map_err(|error| eprintln!("{}", error))
+                .is_ok()
+            });
+        let renderer = Renderer::new(simulation_runner.grid(), &simulation, window);
         let observer = Observer::new(window);
 
         Game {
@@ -39,7 +152,13 @@ impl<S: Simulation> colosseum::Game for Game<S> {
             renderer,
             tick_time: 0.0,
             time_scale: simulation.time_scale(),
-            phantom: PhantomData,
+            potential_update_interval: simulation.simulation_settings().potential_update_interval(),
+            monitor,
+            recorder,
+            checkpointing,
+            snapshots,
+            halted: false,
+            simulation,
         }
     }
 
@@ -47,15 +166,68 @@ impl<S: Simulation> colosseum::Game for Game<S> {
         // Camera update
         self.observer.update(delta_time, window);
 
-        // Physics update
+        // Physics update, which stops for good if the diagnostics abort
+        if self.halted {
+            return;
+        }
+
         self.tick_time += delta_time / self.time_scale;
         if self.tick_time >= self.simulation_runner.dt() {
             while self.tick_time >= self.simulation_runner.dt() {
-                self.simulation_runner.update(window);
+                self.simulation_runner.step(window);
                 self.tick_time -= self.simulation_runner.dt();
+
+                if let Some(interval) = self.potential_update_interval {
+                    if self.simulation_runner.steps().is_multiple_of(interval) {
+                        if let Err(error) = update_potential(
+                            self.simulation_runner.as_mut(),
+                            &self.simulation,
+                            window,
+                        ) {
+                            eprintln!("{}", error);
+                        }
+                    }
+                }
+
+                // Recording stops on a write error, the simulation carries on without it
+                if let Some(recorder) = &mut self.recorder {
+                    if let Err(error) = recorder.update(self.simulation_runner.as_mut(), window) {
+                        eprintln!("{}", error);
+                        self.recorder = None;
+                    }
+                }
+
+                // Likewise for checkpoints and snapshots
+                if let Some(checkpointing) = self.checkpointing {
+                    if let Err(error) =
+                        checkpoint::update(checkpointing, self.simulation_runner.as_mut(), window)
+                    {
+                        eprintln!("{}", error);
+                        self.checkpointing = None;
+                    }
+                }
+
+                if let Some(snapshots) = self.snapshots {
+                    let units = self.simulation.simulation_settings().units();
+                    if let Err(error) =
+                        snapshot::update(snapshots, units, self.simulation_runner.as_mut(), window)
+                    {
+                        eprintln!("{}", error);
+                        self.snapshots = None;
+                    }
+                }
+
+                if let Some(monitor) = &mut self.monitor {
+                    if let Err(error) = monitor.update(self.simulation_runner.as_mut(), window) {
+                        eprintln!("{}", error);
+                        self.halted = true;
+                        break;
+                    }
+                }
             }
 
-            self.renderer.update(&mut self.simulation_runner, window);
+            self.renderer
+                .update(self.simulation_runner.as_mut(), window);
         }
     }
 
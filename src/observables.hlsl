@@ -0,0 +1,125 @@
+cbuffer Settings {
+    float2 DTH_2MI; // dt * h_bar / (2 * m * i)
+    float DX2; // dx ^ 2
+    float DY2; // dy ^ 2
+    float2 DT_HI; // dt / (h_bar * i)
+    uint NUM_POINTS_X;
+    uint NUM_POINTS_Y;
+    uint INTEGRATOR;
+    uint BOUNDARY_X;
+    uint BOUNDARY_Y;
+    float POTENTIAL_SCALE; // converts potentials into hartrees
+}
+
+static const uint NEUMANN = 0;
+static const uint DIRICHLET = 1;
+static const uint PERIODIC = 2;
+
+// Matches `NUM_SUMS` in `observables.rs`
+static const uint NUM_SUMS = 9;
+static const uint GROUP_SIZE = 16;
+
+RWStructuredBuffer<float2> current_wave : register(u1);
+
+// NUM_SUMS partial sums for every thread group, added up on the CPU
+RWStructuredBuffer<float> sums : register(u6);
+
+groupshared float partial_sums[NUM_SUMS][GROUP_SIZE * GROUP_SIZE];
+
+uint index(uint x, uint y) {
+    return x + y * NUM_POINTS_X;
+}
+
+float2 c_mul(float2 c1, float2 c2) {
+	return float2(c1.x  *c2.x - c1.y * c2.y, c1.y * c2.x + c1.x * c2.y);
+}
+
+float2 c_conj(float2 c) {
+    return float2(c.x, -c.y);
+}
+
+// Finds the neighbour `offset` away from `i` along an axis of `n` points. Returns false if the
+// neighbour lies on a Dirichlet wall.
+bool neighbour(uint i, int offset, uint n, uint boundary, out uint j) {
+    int k = int(i) + offset;
+    j = i;
+
+    if (k >= 0 && k < int(n)) {
+        j = uint(k);
+    } else if (boundary == DIRICHLET) {
+        return false;
+    } else if (boundary == PERIODIC) {
+        j = uint((k + int(n)) % int(n));
+    }
+
+    return true;
+}
+
+float2 neighbour_x(uint2 tid, int offset) {
+    uint x;
+    if (!neighbour(tid.x, offset, NUM_POINTS_X, BOUNDARY_X, x)) {
+        return float2(0.0, 0.0);
+    }
+    return current_wave[index(x, tid.y)];
+}
+
+float2 neighbour_y(uint2 tid, int offset) {
+    uint y;
+    if (!neighbour(tid.y, offset, NUM_POINTS_Y, BOUNDARY_Y, y)) {
+        return float2(0.0, 0.0);
+    }
+    return current_wave[index(tid.x, y)];
+}
+
+[numthreads(GROUP_SIZE, GROUP_SIZE, 1)]
+void compute_main(uint3 tid : SV_DispatchThreadID, uint3 gid : SV_GroupID, uint gi : SV_GroupIndex) {
+    uint k;
+    for (k = 0; k < NUM_SUMS; k++) {
+        partial_sums[k][gi] = 0.0;
+    }
+
+    // Threads hanging over the edge of the grid contribute nothing
+    if (tid.x < NUM_POINTS_X && tid.y < NUM_POINTS_Y) {
+        float2 psi_now = current_wave[index(tid.x, tid.y)];
+        float2 psi_x_u = neighbour_x(tid.xy, 1);
+        float2 psi_x_l = neighbour_x(tid.xy, -1);
+        float2 psi_y_u = neighbour_y(tid.xy, 1);
+        float2 psi_y_l = neighbour_y(tid.xy, -1);
+
+        float density = psi_now.x * psi_now.x + psi_now.y * psi_now.y;
+        float i = float(tid.x);
+        float j = float(tid.y);
+        float2 psi_conj = c_conj(psi_now);
+
+        partial_sums[0][gi] = density;
+        partial_sums[1][gi] = i * density;
+        partial_sums[2][gi] = j * density;
+        partial_sums[3][gi] = i * i * density;
+        partial_sums[4][gi] = j * j * density;
+        partial_sums[5][gi] = c_mul(psi_conj, psi_x_u - psi_x_l).y;
+        partial_sums[6][gi] = c_mul(psi_conj, psi_y_u - psi_y_l).y;
+        partial_sums[7][gi] = c_mul(psi_conj, psi_x_u - 2.0 * psi_now + psi_x_l).x;
+        partial_sums[8][gi] = c_mul(psi_conj, psi_y_u - 2.0 * psi_now + psi_y_l).x;
+    }
+
+    GroupMemoryBarrierWithGroupSync();
+
+    // Tree reduction within the thread group
+    for (uint stride = GROUP_SIZE * GROUP_SIZE / 2; stride > 0; stride /= 2) {
+        if (gi < stride) {
+            for (k = 0; k < NUM_SUMS; k++) {
+                partial_sums[k][gi] += partial_sums[k][gi + stride];
+            }
+        }
+
+        GroupMemoryBarrierWithGroupSync();
+    }
+
+    if (gi == 0) {
+        uint num_groups_x = (NUM_POINTS_X + GROUP_SIZE - 1) / GROUP_SIZE;
+        uint group = gid.x + gid.y * num_groups_x;
+        for (k = 0; k < NUM_SUMS; k++) {
+            sums[group * NUM_SUMS + k] = partial_sums[k][0];
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{boundary, settings::Settings, Complex, Grid, Real};
+
+// Expectation values of the current psi, in the simulation's units. They are kept in double
+// precision since squared momenta in SI units are far below the smallest f32.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Observables {
+    steps: usize,
+    time: f64,
+    norm: f64,
+    x: f64,
+    y: f64,
+    x2: f64,
+    y2: f64,
+    px: f64,
+    py: f64,
+    px2: f64,
+    py2: f64,
+}
+
+// The sums over the grid every observable is built from, each computed per point as
+//   |psi|^2, i |psi|^2, j |psi|^2, i^2 |psi|^2, j^2 |psi|^2,
+//   Im(psi* (psi_{i+1} - psi_{i-1})), Im(psi* (psi_{j+1} - psi_{j-1})),
+//   Re(psi* (psi_{i+1} - 2 psi + psi_{i-1})), Re(psi* (psi_{j+1} - 2 psi + psi_{j-1}))
+// where i and j are the point's indices along x and y. `observables.hlsl` computes the same sums.
+pub(crate) const NUM_SUMS: usize = 9;
+
+impl Observables {
+    // Converts the sums from grid indices and differences into positions and momenta
+    pub(crate) fn from_sums(
+        sums: [f64; NUM_SUMS],
+        grid: &Grid,
+        h_bar: f64,
+        steps: usize,
+        time: f64,
+    ) -> Self {
+        let dx = grid.dx() as f64;
+        let dy = grid.dy() as f64;
+        let base_x = -(grid.width() as f64) / 2.0;
+        let base_y = -(grid.height() as f64) / 2.0;
+
+        // A zero wave function has no expectation values, they are all left at zero
+        let total = sums[0];
+        let mean = |sum: f64| if total > 0.0 { sum / total } else { 0.0 };
+        let (i, j, i2, j2) = (mean(sums[1]), mean(sums[2]), mean(sums[3]), mean(sums[4]));
+
+        Observables {
+            steps,
+            time,
+            norm: total * dx * dy,
+            x: base_x + dx * i,
+            y: base_y + dy * j,
+            x2: base_x * base_x + 2.0 * base_x * dx * i + dx * dx * i2,
+            y2: base_y * base_y + 2.0 * base_y * dy * j + dy * dy * j2,
+            px: h_bar * mean(sums[5]) / (2.0 * dx),
+            py: h_bar * mean(sums[6]) / (2.0 * dy),
+            px2: -h_bar * h_bar * mean(sums[7]) / (dx * dx),
+            py2: -h_bar * h_bar * mean(sums[8]) / (dy * dy),
+        }
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    // The sum of |psi|^2 dx dy, every other observable is normalized by it
+    pub fn norm(&self) -> f64 {
+        self.norm
+    }
+
+    // <x>
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    // <y>
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    // <x^2>
+    pub fn x2(&self) -> f64 {
+        self.x2
+    }
+
+    // <y^2>
+    pub fn y2(&self) -> f64 {
+        self.y2
+    }
+
+    // <p_x>, from the central difference of psi
+    pub fn px(&self) -> f64 {
+        self.px
+    }
+
+    // <p_y>, from the central difference of psi
+    pub fn py(&self) -> f64 {
+        self.py
+    }
+
+    // <p_x^2>, from the same discrete Laplacian the integrators use
+    pub fn px2(&self) -> f64 {
+        self.px2
+    }
+
+    // <p_y^2>, from the same discrete Laplacian the integrators use
+    pub fn py2(&self) -> f64 {
+        self.py2
+    }
+
+    pub fn delta_x(&self) -> f64 {
+        spread(self.x, self.x2)
+    }
+
+    pub fn delta_y(&self) -> f64 {
+        spread(self.y, self.y2)
+    }
+
+    pub fn delta_px(&self) -> f64 {
+        spread(self.px, self.px2)
+    }
+
+    pub fn delta_py(&self) -> f64 {
+        spread(self.py, self.py2)
+    }
+
+    // dx * dp_x, at least h_bar / 2
+    pub fn uncertainty_x(&self) -> f64 {
+        self.delta_x() * self.delta_px()
+    }
+
+    // dy * dp_y, at least h_bar / 2
+    pub fn uncertainty_y(&self) -> f64 {
+        self.delta_y() * self.delta_py()
+    }
+}
+
+// The CPU version of the reduction in `observables.hlsl`, accumulated in double precision
+pub(crate) fn sums<T: Real>(settings: &Settings<T>, psi: &[Complex<T>]) -> [f64; NUM_SUMS] {
+    let num_points_x = settings.num_points_x as usize;
+    let num_points_y = settings.num_points_y as usize;
+
+    let mut sums = [0.0; NUM_SUMS];
+    for y in 0..num_points_y {
+        for x in 0..num_points_x {
+            let psi_at = |x: Option<usize>, y: Option<usize>| match (x, y) {
+                (Some(x), Some(y)) => psi[x + y * num_points_x].cast::<f64>(),
+                _ => Complex::ZERO,
+            };
+
+            let psi_now = psi[x + y * num_points_x].cast::<f64>();
+            let psi_x_u = psi_at(
+                boundary::upper(x, num_points_x, settings.boundary_x),
+                Some(y),
+            );
+            let psi_x_l = psi_at(
+                boundary::lower(x, num_points_x, settings.boundary_x),
+                Some(y),
+            );
+            let psi_y_u = psi_at(
+                Some(x),
+                boundary::upper(y, num_points_y, settings.boundary_y),
+            );
+            let psi_y_l = psi_at(
+                Some(x),
+                boundary::lower(y, num_points_y, settings.boundary_y),
+            );
+
+            let density = psi_now.norm_sqr();
+            let (i, j) = (x as f64, y as f64);
+            let psi_conj = psi_now.conj();
+
+            sums[0] += density;
+            sums[1] += i * density;
+            sums[2] += j * density;
+            sums[3] += i * i * density;
+            sums[4] += j * j * density;
+            sums[5] += (psi_conj * (psi_x_u - psi_x_l)).im;
+            sums[6] += (psi_conj * (psi_y_u - psi_y_l)).im;
+            sums[7] += (psi_conj * (psi_x_u - psi_now * 2.0 + psi_x_l)).re;
+            sums[8] += (psi_conj * (psi_y_u - psi_now * 2.0 + psi_y_l)).re;
+        }
+    }
+    sums
+}
+
+// Standard deviation from the first two moments, clamped at zero against rounding
+fn spread(mean: f64, mean_square: f64) -> f64 {
+    (mean_square - mean * mean).max(0.0).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        states::{GaussianPacket, InitialState},
+        testing::TestSimulation,
+        Complex, Simulation, SimulationSettings, Solver, UnitSystem,
+    };
+
+    // A packet at (1, -2) with k = (1.5, -0.5) on a 160 x 160 grid, every length scaled by
+    // `length` and every momentum by `h_bar / length`
+    fn check_packet(units: UnitSystem, length: f32, psi_0: fn(f32, f32) -> Complex) {
+        let simulation = TestSimulation {
+            settings: SimulationSettings::new(160, 160, 0.1 * length, 0.1 * length, 1.0, 1.0)
+                .with_units(units),
+            psi_0,
+            ..TestSimulation::new()
+        };
+        let observables = Solver::<f64>::with_precision(&simulation)
+            .unwrap()
+            .observables();
+
+        let length = length as f64;
+        let momentum = units.h_bar() / length;
+        assert!((observables.x() / length - 1.0).abs() < 1e-4);
+        assert!((observables.y() / length + 2.0).abs() < 1e-4);
+
+        // The central difference sees sin(k dx) / dx, 0.4% below k at k dx = 0.15
+        assert!(
+            (observables.px() / momentum - 1.5).abs() < 1e-2 * 1.5,
+            "{:?} px = {}",
+            units,
+            observables.px() / momentum
+        );
+        assert!(
+            (observables.py() / momentum + 0.5).abs() < 1e-2 * 0.5,
+            "{:?} py = {}",
+            units,
+            observables.py() / momentum
+        );
+    }
+
+    #[test]
+    fn packet_has_its_position_and_momentum() {
+        check_packet(UnitSystem::Dimensionless, 1.0, |x, y| {
+            GaussianPacket::new(1.0, -2.0, 1.0, 1.5, -0.5).psi(x, y)
+        });
+
+        // The same packet in nanometres
+        check_packet(UnitSystem::Si, 1e-9, |x, y| {
+            GaussianPacket::new(1e-9, -2e-9, 1e-9, 1.5e9, -0.5e9).psi(x, y)
+        });
+    }
+}
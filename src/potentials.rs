@@ -0,0 +1,286 @@
+// Ready made potentials. Each one can be returned from `Simulation::potential` as
+// `self.potential.v(x, y)`, and any number of them can be added together. Lengths and energies are
+// in the simulation's units. An infinite potential is a hard wall that psi cannot enter.
+
+pub trait Potential {
+    fn v(&self, x: f32, y: f32) -> f32;
+
+    fn plus<P: Potential>(self, other: P) -> Plus<Self, P>
+    where
+        Self: Sized,
+    {
+        Plus(self, other)
+    }
+}
+
+// Any `Fn(x, y) -> f32` is a potential, so hand written potentials mix with the ones here
+impl<F: Fn(f32, f32) -> f32> Potential for F {
+    fn v(&self, x: f32, y: f32) -> f32 {
+        self(x, y)
+    }
+}
+
+// The sum of two potentials, see `Potential::plus`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Plus<A, B>(pub A, pub B);
+
+// The sum of any number of potentials
+#[derive(Default)]
+pub struct Sum {
+    terms: Vec<Box<dyn Potential>>,
+}
+
+// m * (omega_x^2 * (x - x0)^2 + omega_y^2 * (y - y0)^2) / 2
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HarmonicTrap {
+    mass: f32,
+    omega_x: f32,
+    omega_y: f32,
+    x0: f32,
+    y0: f32,
+}
+
+// A `width` by `height` rectangle centred on the origin, with one value inside and another outside
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SquareWell {
+    width: f32,
+    height: f32,
+    inside: f32,
+    outside: f32,
+}
+
+// A strip of the given thickness and height across the whole grid, perpendicular to x
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Barrier {
+    x0: f32,
+    thickness: f32,
+    height: f32,
+}
+
+// A barrier with `count` openings of `slit_width`, `separation` apart centre to centre and
+// arranged symmetrically about y = 0
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SlitWall {
+    barrier: Barrier,
+    count: usize,
+    slit_width: f32,
+    separation: f32,
+}
+
+// -strength / sqrt(|r - r_0|^2 + softening^2), a 1/r attraction kept finite at the centre
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SoftCoulomb {
+    strength: f32,
+    softening: f32,
+    x0: f32,
+    y0: f32,
+}
+
+// depth * (sin^2(pi * x / period_x) + sin^2(pi * y / period_y)), with a minimum on every site of a
+// rectangular lattice through the origin
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Lattice {
+    depth: f32,
+    period_x: f32,
+    period_y: f32,
+}
+
+impl<A: Potential, B: Potential> Potential for Plus<A, B> {
+    fn v(&self, x: f32, y: f32) -> f32 {
+        self.0.v(x, y) + self.1.v(x, y)
+    }
+}
+
+impl Sum {
+    pub fn new() -> Self {
+        Sum { terms: Vec::new() }
+    }
+
+    pub fn with<P: Potential + 'static>(mut self, potential: P) -> Self {
+        self.terms.push(Box::new(potential));
+        self
+    }
+}
+
+impl Potential for Sum {
+    fn v(&self, x: f32, y: f32) -> f32 {
+        self.terms.iter().map(|potential| potential.v(x, y)).sum()
+    }
+}
+
+impl HarmonicTrap {
+    pub fn new(mass: f32, omega: f32) -> Self {
+        HarmonicTrap::anisotropic(mass, omega, omega)
+    }
+
+    pub fn anisotropic(mass: f32, omega_x: f32, omega_y: f32) -> Self {
+        HarmonicTrap {
+            mass,
+            omega_x,
+            omega_y,
+            x0: 0.0,
+            y0: 0.0,
+        }
+    }
+
+    pub fn with_center(self, x0: f32, y0: f32) -> Self {
+        HarmonicTrap { x0, y0, ..self }
+    }
+}
+
+impl Potential for HarmonicTrap {
+    fn v(&self, x: f32, y: f32) -> f32 {
+        let dx = self.omega_x * (x - self.x0);
+        let dy = self.omega_y * (y - self.y0);
+        0.5 * self.mass * (dx * dx + dy * dy)
+    }
+}
+
+impl SquareWell {
+    // -depth inside and 0 outside
+    pub fn finite(width: f32, height: f32, depth: f32) -> Self {
+        SquareWell {
+            width,
+            height,
+            inside: -depth,
+            outside: 0.0,
+        }
+    }
+
+    // 0 inside and a hard wall outside
+    pub fn infinite(width: f32, height: f32) -> Self {
+        SquareWell {
+            width,
+            height,
+            inside: 0.0,
+            outside: f32::INFINITY,
+        }
+    }
+}
+
+impl Potential for SquareWell {
+    fn v(&self, x: f32, y: f32) -> f32 {
+        if x.abs() <= self.width / 2.0 && y.abs() <= self.height / 2.0 {
+            self.inside
+        } else {
+            self.outside
+        }
+    }
+}
+
+impl Barrier {
+    pub fn new(x0: f32, thickness: f32, height: f32) -> Self {
+        Barrier {
+            x0,
+            thickness,
+            height,
+        }
+    }
+
+    // Two barriers centred on x0, `separation` apart centre to centre
+    pub fn double(x0: f32, thickness: f32, separation: f32, height: f32) -> Plus<Barrier, Barrier> {
+        Barrier::new(x0 - separation / 2.0, thickness, height).plus(Barrier::new(
+            x0 + separation / 2.0,
+            thickness,
+            height,
+        ))
+    }
+
+    fn contains(&self, x: f32) -> bool {
+        (x - self.x0).abs() <= self.thickness / 2.0
+    }
+}
+
+impl Potential for Barrier {
+    fn v(&self, x: f32, _: f32) -> f32 {
+        if self.contains(x) {
+            self.height
+        } else {
+            0.0
+        }
+    }
+}
+
+impl SlitWall {
+    pub fn new(
+        x0: f32,
+        thickness: f32,
+        height: f32,
+        count: usize,
+        slit_width: f32,
+        separation: f32,
+    ) -> Self {
+        SlitWall {
+            barrier: Barrier::new(x0, thickness, height),
+            count,
+            slit_width,
+            separation,
+        }
+    }
+
+    pub fn single(x0: f32, thickness: f32, height: f32, slit_width: f32) -> Self {
+        SlitWall::new(x0, thickness, height, 1, slit_width, 0.0)
+    }
+
+    pub fn double(x0: f32, thickness: f32, height: f32, slit_width: f32, separation: f32) -> Self {
+        SlitWall::new(x0, thickness, height, 2, slit_width, separation)
+    }
+
+    fn in_slit(&self, y: f32) -> bool {
+        (0..self.count).any(|i| {
+            let center = (i as f32 - (self.count - 1) as f32 / 2.0) * self.separation;
+            (y - center).abs() <= self.slit_width / 2.0
+        })
+    }
+}
+
+impl Potential for SlitWall {
+    fn v(&self, x: f32, y: f32) -> f32 {
+        if self.in_slit(y) {
+            0.0
+        } else {
+            self.barrier.v(x, y)
+        }
+    }
+}
+
+impl SoftCoulomb {
+    pub fn new(strength: f32, softening: f32) -> Self {
+        SoftCoulomb {
+            strength,
+            softening,
+            x0: 0.0,
+            y0: 0.0,
+        }
+    }
+
+    pub fn with_center(self, x0: f32, y0: f32) -> Self {
+        SoftCoulomb { x0, y0, ..self }
+    }
+}
+
+impl Potential for SoftCoulomb {
+    fn v(&self, x: f32, y: f32) -> f32 {
+        let dx = x - self.x0;
+        let dy = y - self.y0;
+        -self.strength / (dx * dx + dy * dy + self.softening * self.softening).sqrt()
+    }
+}
+
+impl Lattice {
+    pub fn new(depth: f32, period_x: f32, period_y: f32) -> Self {
+        Lattice {
+            depth,
+            period_x,
+            period_y,
+        }
+    }
+}
+
+impl Potential for Lattice {
+    fn v(&self, x: f32, y: f32) -> f32 {
+        let sx = (std::f32::consts::PI * x / self.period_x).sin();
+        let sy = (std::f32::consts::PI * y / self.period_y).sin();
+        self.depth * (sx * sx + sy * sy)
+    }
+}
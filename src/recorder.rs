@@ -0,0 +1,253 @@
+use crate::{Error, Measurement, Observables, Real, RecordFormat, Recording, SolverBackend};
+use std::io::Write;
+
+const COLUMNS: [&str; 16] = [
+    "step", "t", "norm", "energy", "x", "y", "x2", "y2", "px", "py", "px2", "py2", "delta_x",
+    "delta_y", "delta_px", "delta_py",
+];
+
+// Writes the time series a `Recording` asks for. Every row is flushed as it is written so the file
+// can be read while the simulation is still running.
+pub(crate) struct Recorder {
+    recording: Recording,
+    file: std::io::BufWriter<std::fs::File>,
+}
+
+impl Recorder {
+    // Creates the file and records the backend's current state as the first row. A run resumed from
+    // a checkpoint instead continues the file the earlier run wrote, if there is one.
+    pub fn new<C, T: Real, B: SolverBackend<C, T> + ?Sized>(
+        recording: Recording,
+        resumed: bool,
+        backend: &mut B,
+        context: &mut C,
+    ) -> Result<Self, Error> {
+        let error = |error: std::io::Error| Error::Recording {
+            path: recording.path(),
+            kind: error.kind(),
+        };
+
+        if resumed {
+            if let Some(file) = reopen(recording.path(), backend.steps()).map_err(error)? {
+                return Ok(Recorder {
+                    recording,
+                    file: std::io::BufWriter::new(file),
+                });
+            }
+        }
+
+        let file = std::fs::File::create(recording.path()).map_err(error)?;
+
+        let mut recorder = Recorder {
+            recording,
+            file: std::io::BufWriter::new(file),
+        };
+
+        if recording.format() == RecordFormat::Csv {
+            let header = COLUMNS.join(",");
+            recorder.write(&header)?;
+        }
+
+        recorder.record(backend, context)?;
+        Ok(recorder)
+    }
+
+    // Records a row if one is due
+    pub fn update<C, T: Real, B: SolverBackend<C, T> + ?Sized>(
+        &mut self,
+        backend: &mut B,
+        context: &mut C,
+    ) -> Result<(), Error> {
+        let interval = self.recording.interval();
+        if interval == 0 || !backend.steps().is_multiple_of(interval) {
+            return Ok(());
+        }
+
+        self.record(backend, context)
+    }
+
+    fn record<C, T: Real, B: SolverBackend<C, T> + ?Sized>(
+        &mut self,
+        backend: &mut B,
+        context: &mut C,
+    ) -> Result<(), Error> {
+        let measurement = backend.measure(context);
+        let time = measurement.time();
+        let values = values(&measurement, &backend.observables(context));
+
+        let line = match self.recording.format() {
+            RecordFormat::Csv => {
+                let mut fields = vec![measurement.steps().to_string(), format!("{:e}", time)];
+                fields.extend(values.iter().map(|value| format!("{:e}", value)));
+                fields.join(",")
+            }
+            RecordFormat::JsonLines => {
+                let mut fields = vec![
+                    format!("\"{}\":{}", COLUMNS[0], measurement.steps()),
+                    format!("\"{}\":{:e}", COLUMNS[1], time),
+                ];
+                fields.extend(
+                    COLUMNS[2..]
+                        .iter()
+                        .zip(values)
+                        .map(|(column, value)| format!("\"{}\":{}", column, json_number(value))),
+                );
+                format!("{{{}}}", fields.join(","))
+            }
+        };
+
+        self.write(&line)
+    }
+
+    fn write(&mut self, line: &str) -> Result<(), Error> {
+        writeln!(self.file, "{}", line)
+            .and_then(|_| self.file.flush())
+            .map_err(|error| Error::Recording {
+                path: self.recording.path(),
+                kind: error.kind(),
+            })
+    }
+}
+
+// Opens a recording to append to, cut back to the rows up to `steps` so rows the earlier run wrote
+// after its last checkpoint aren't repeated. `None` if there is no recording yet.
+fn reopen(path: &str, steps: usize) -> std::io::Result<Option<std::fs::File>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+
+    // A row cut short by the earlier run ending is dropped along with everything after it
+    let mut length = 0;
+    for line in contents.split_inclusive('\n') {
+        if !line.ends_with('\n') || row_step(line).is_some_and(|step| step > steps) {
+            break;
+        }
+        length += line.len();
+    }
+
+    let file = std::fs::OpenOptions::new().append(true).open(path)?;
+    file.set_len(length as u64)?;
+    Ok(Some(file))
+}
+
+// The step a row was recorded at, `None` for the CSV header
+fn row_step(line: &str) -> Option<usize> {
+    let line = line
+        .strip_prefix(&format!("{{\"{}\":", COLUMNS[0]))
+        .unwrap_or(line);
+    line.split([',', '}']).next()?.parse().ok()
+}
+
+// Every column of a row after the step and time, in the order of `COLUMNS`
+fn values<T: Real>(measurement: &Measurement<T>, observables: &Observables) -> [f64; 14] {
+    [
+        measurement.norm().to_f64(),
+        measurement.energy().to_f64(),
+        observables.x(),
+        observables.y(),
+        observables.x2(),
+        observables.y2(),
+        observables.px(),
+        observables.py(),
+        observables.px2(),
+        observables.py2(),
+        observables.delta_x(),
+        observables.delta_y(),
+        observables.delta_px(),
+        observables.delta_py(),
+    ]
+}
+
+// JSON has no NaN or infinity, a run that blew up records them as null
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:e}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::TestSimulation, Checkpointing, RecordFormat, Recording, Simulation, Snapshots,
+        Solver,
+    };
+
+    #[test]
+    fn times_agree_with_snapshots() {
+        let directory = std::env::temp_dir().join(format!("wave-recording-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("recording.csv");
+        let simulation = TestSimulation {
+            settings: TestSimulation::new()
+                .settings
+                .with_recording(Recording::csv(Box::leak(path.to_str().unwrap().into()), 3))
+                .with_snapshots(Snapshots::new(
+                    Box::leak(directory.to_str().unwrap().into()),
+                    3,
+                )),
+            ..TestSimulation::new()
+        };
+
+        let mut solver = Solver::new(&simulation).unwrap();
+        solver.step(9).unwrap();
+
+        // The time column against the sidecar written at the same step
+        let recording = std::fs::read_to_string(&path).unwrap();
+        for row in recording.lines().skip(1) {
+            let fields: Vec<&str> = row.split(',').collect();
+            let step: usize = fields[0].parse().unwrap();
+            let sidecar =
+                std::fs::read_to_string(directory.join(format!("snapshot_{:08}.json", step)))
+                    .unwrap();
+            assert!(
+                sidecar.contains(&format!("\"time\":{},", fields[1])),
+                "{} not in {}",
+                fields[1],
+                sidecar
+            );
+        }
+        assert_eq!(recording.lines().count(), 5);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn resumed_runs_continue_the_recording() {
+        for format in [RecordFormat::Csv, RecordFormat::JsonLines] {
+            let directory = std::env::temp_dir().join(format!(
+                "wave-resumed-{:?}-{}",
+                format,
+                std::process::id()
+            ));
+            std::fs::create_dir_all(&directory).unwrap();
+            let path: &'static str =
+                Box::leak(directory.join("recording").to_str().unwrap().into());
+            let checkpoint: &'static str =
+                Box::leak(directory.join("checkpoint").to_str().unwrap().into());
+            let simulation = TestSimulation {
+                settings: TestSimulation::new()
+                    .settings
+                    .with_recording(Recording::new(path, 2, format))
+                    .with_checkpointing(Checkpointing::new(checkpoint, 5).with_resume(true)),
+                ..TestSimulation::new()
+            };
+
+            // The uninterrupted recording, then one stopped at step 13 and resumed from step 10
+            Solver::new(&simulation).unwrap().step(20).unwrap();
+            let expected = std::fs::read_to_string(path).unwrap();
+            std::fs::remove_file(checkpoint).unwrap();
+
+            Solver::new(&simulation).unwrap().step(13).unwrap();
+            let mut resumed = Solver::new(&simulation).unwrap();
+            assert_eq!(resumed.steps(), 10);
+            resumed.step(10).unwrap();
+
+            assert_eq!(std::fs::read_to_string(path).unwrap(), expected);
+            std::fs::remove_dir_all(directory).unwrap();
+        }
+    }
+}
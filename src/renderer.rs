@@ -1,15 +1,29 @@
-use crate::{simulation_runner::SimulationRunner, Simulation};
+use crate::{
+    resample::{resample, ResampleSettings},
+    Density, Grid, Simulation, SolverBackend,
+};
 use colosseum::{Input, Vertex, Window};
 
 pub struct Renderer {
     mesh: colosseum::MeshRenderer,
     shader: colosseum::Shader,
+
+    // The density resampled onto the render mesh
     texture: alexandria::Texture,
+
+    num_thread_groups_x: usize,
+    num_thread_groups_y: usize,
+    resample_settings: ResampleSettings,
+    resample_settings_buffer: alexandria::ConstantBuffer<ResampleSettings>,
+    resample_shader: alexandria::compute::ComputeShader,
 }
 
+// Matches `numthreads` in `resample.hlsl`
+const THREAD_GROUP_SIZE: usize = 16;
+
 impl Renderer {
     pub fn new<I: Input, S: Simulation>(
-        simulation_runner: &SimulationRunner,
+        grid: &Grid,
         simulation: &S,
         window: &mut Window<I>,
     ) -> Self {
@@ -19,20 +33,24 @@ impl Renderer {
         let mut indices =
             Vec::with_capacity((settings.num_points_x() - 1) * (settings.num_points_y() - 1) * 6);
 
-        let base_x = -(simulation_runner.width() / 2.0);
-        let base_y = -(simulation_runner.height() / 2.0);
+        // The mesh spans the solver grid whatever its own resolution
+        let u = |x: usize| mesh_position(x, settings.num_points_x());
+        let v = |y: usize| mesh_position(y, settings.num_points_y());
+
+        let base_x = -(grid.width() / 2.0);
+        let base_y = -(grid.height() / 2.0);
         for y in 0..settings.num_points_y() {
             for x in 0..settings.num_points_x() {
                 vertices.push(Vertex::new(
-                    base_x + (x as f32) * simulation_runner.dx(),
+                    base_x + u(x) * grid.width(),
                     0.0,
-                    base_y + (y as f32) * simulation_runner.dy(),
+                    base_y + v(y) * grid.height(),
                     1.0,
                     1.0,
                     1.0,
                     1.0,
-                    x as f32 / settings.num_points_x() as f32,
-                    y as f32 / settings.num_points_y() as f32,
+                    u(x),
+                    v(y),
                 ));
 
                 if x != settings.num_points_x() - 1 && y != settings.num_points_y() - 1 {
@@ -65,28 +83,65 @@ impl Renderer {
 
         let shader = colosseum::Shader::new(include_str!("shader.hlsl"), window);
 
-        let initial_values =
-            vec![0.0; simulation_runner.num_points_x() * simulation_runner.num_points_y()];
+        let initial_values = vec![0.0; settings.num_points_x() * settings.num_points_y()];
         let texture = alexandria::Texture::new_1f(
             initial_values.as_slice(),
-            simulation_runner.num_points_x(),
+            settings.num_points_x(),
             0,
             window.inner(),
         );
 
+        let resample_settings = ResampleSettings::new(
+            grid.num_points_x(),
+            grid.num_points_y(),
+            settings.num_points_x(),
+            settings.num_points_y(),
+            settings.resampling(),
+        );
+        let resample_settings_buffer =
+            alexandria::ConstantBuffer::new(Some(resample_settings), 0, window.inner()).unwrap();
+        let resample_shader =
+            alexandria::compute::ComputeShader::new(include_str!("resample.hlsl"), window.inner())
+                .unwrap();
+
         Renderer {
             mesh,
             shader,
             texture,
+            num_thread_groups_x: settings.num_points_x().div_ceil(THREAD_GROUP_SIZE),
+            num_thread_groups_y: settings.num_points_y().div_ceil(THREAD_GROUP_SIZE),
+            resample_settings,
+            resample_settings_buffer,
+            resample_shader,
         }
     }
 
-    pub fn update<I: Input>(&mut self, simulation: &mut SimulationRunner, window: &mut Window<I>) {
-        window.inner().device_context().flush();
-        window
-            .inner()
-            .device_context()
-            .copy_resource(self.texture.inner_mut(), simulation.output().inner_mut())
+    pub fn update<I: Input>(
+        &mut self,
+        backend: &mut dyn SolverBackend<Window<I>>,
+        window: &mut Window<I>,
+    ) {
+        match backend.density(window) {
+            Density::Texture(output) => {
+                self.resample_shader.set_active(window.inner());
+                output.set_active_compute_rw(window.inner());
+                self.texture.set_active_compute_rw(window.inner());
+                self.resample_settings_buffer
+                    .set_active_compute(window.inner());
+
+                self.resample_shader.dispatch(
+                    self.num_thread_groups_x,
+                    self.num_thread_groups_y,
+                    1,
+                    window.inner(),
+                );
+            }
+            Density::Values(output) => {
+                // Writes into the texture made in `new` rather than making one every frame
+                let values = resample(output, &self.resample_settings);
+                self.texture.update_1f(&values, window.inner());
+            }
+        }
     }
 
     pub fn render<I: Input>(&mut self, window: &mut Window<I>) {
@@ -95,3 +150,12 @@ impl Renderer {
         self.mesh.render(window);
     }
 }
+
+// Position of mesh point `i` of `n` as a fraction of the grid
+fn mesh_position(i: usize, n: usize) -> f32 {
+    if n < 2 {
+        return 0.5;
+    }
+
+    i as f32 / (n - 1) as f32
+}
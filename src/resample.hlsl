@@ -0,0 +1,80 @@
+cbuffer ResampleSettings {
+    uint NUM_POINTS_X;
+    uint NUM_POINTS_Y;
+    uint RENDER_POINTS_X;
+    uint RENDER_POINTS_Y;
+    uint RESAMPLING;
+    uint3 reserved;
+}
+
+static const uint BILINEAR = 0;
+static const uint MAX_POOL = 1;
+
+RWTexture2D<float> display : register(u0);
+RWTexture2D<float> density : register(u3);
+
+// Position of render point `i` of `render_points`, in solver grid points
+float position(uint i, uint render_points, uint num_points) {
+    if (render_points < 2) {
+        return float(num_points - 1) / 2.0;
+    }
+
+    return float(i) * float(num_points - 1) / float(render_points - 1);
+}
+
+float bilinear(float2 uv) {
+    uint2 last = uint2(NUM_POINTS_X - 1, NUM_POINTS_Y - 1);
+    uint2 p0 = min(uint2(floor(uv)), last);
+    uint2 p1 = min(p0 + 1, last);
+    float2 f = uv - float2(p0);
+
+    float bottom = lerp(density[p0], density[uint2(p1.x, p0.y)], f.x);
+    float top = lerp(density[uint2(p0.x, p1.y)], density[p1], f.x);
+    return lerp(bottom, top, f.y);
+}
+
+// The solver points closer to this render point than to its neighbours
+uint2 footprint(float position, uint num_points, uint render_points) {
+    float half_width = float(num_points - 1) / float(max(render_points, 2) - 1) / 2.0;
+    float last = float(num_points - 1);
+
+    float lower = clamp(ceil(position - half_width), 0.0, last);
+    float upper = clamp(floor(position + half_width), 0.0, last);
+    if (lower > upper) {
+        uint nearest = uint(clamp(round(position), 0.0, last));
+        return uint2(nearest, nearest);
+    }
+
+    return uint2(lower, upper);
+}
+
+float max_pool(float2 uv) {
+    uint2 x_range = footprint(uv.x, NUM_POINTS_X, RENDER_POINTS_X);
+    uint2 y_range = footprint(uv.y, NUM_POINTS_Y, RENDER_POINTS_Y);
+
+    float value = density[uint2(x_range.x, y_range.x)];
+    for (uint y = y_range.x; y <= y_range.y; y++) {
+        for (uint x = x_range.x; x <= x_range.y; x++) {
+            value = max(value, density[uint2(x, y)]);
+        }
+    }
+    return value;
+}
+
+[numthreads(16,16,1)]
+void compute_main(uint3 tid : SV_DispatchThreadID) {
+    if (tid.x >= RENDER_POINTS_X || tid.y >= RENDER_POINTS_Y) {
+        return;
+    }
+
+    float2 uv = float2(
+        position(tid.x, RENDER_POINTS_X, NUM_POINTS_X),
+        position(tid.y, RENDER_POINTS_Y, NUM_POINTS_Y)
+    );
+
+    if (RESAMPLING == MAX_POOL) {
+        display[tid.xy] = max_pool(uv);
+    } else {
+        display[tid.xy] = bilinear(uv);
+    }
+}
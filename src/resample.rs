@@ -0,0 +1,180 @@
+use crate::Resampling;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct ResampleSettings {
+    pub num_points_x: u32,
+    pub num_points_y: u32,
+    pub render_points_x: u32,
+    pub render_points_y: u32,
+    pub resampling: u32,
+    pub reserved: [u32; 3],
+}
+
+pub(crate) const BILINEAR: u32 = 0;
+pub(crate) const MAX_POOL: u32 = 1;
+
+impl ResampleSettings {
+    pub fn new(
+        num_points_x: usize,
+        num_points_y: usize,
+        render_points_x: usize,
+        render_points_y: usize,
+        resampling: Resampling,
+    ) -> Self {
+        ResampleSettings {
+            num_points_x: num_points_x as u32,
+            num_points_y: num_points_y as u32,
+            render_points_x: render_points_x as u32,
+            render_points_y: render_points_y as u32,
+            resampling: match resampling {
+                Resampling::Bilinear => BILINEAR,
+                Resampling::MaxPool => MAX_POOL,
+            },
+            reserved: [0; 3],
+        }
+    }
+}
+
+// Mirrors `resample.hlsl` for densities that live on the host
+pub(crate) fn resample(values: &[f32], settings: &ResampleSettings) -> Vec<f32> {
+    let num_points_x = settings.num_points_x as usize;
+    let num_points_y = settings.num_points_y as usize;
+    let render_points_x = settings.render_points_x as usize;
+    let render_points_y = settings.render_points_y as usize;
+
+    let mut resampled = Vec::with_capacity(render_points_x * render_points_y);
+    for y in 0..render_points_y {
+        let v = position(y, render_points_y, num_points_y);
+        for x in 0..render_points_x {
+            let u = position(x, render_points_x, num_points_x);
+
+            resampled.push(if settings.resampling == MAX_POOL {
+                max_pool(values, settings, u, v)
+            } else {
+                bilinear(values, settings, u, v)
+            });
+        }
+    }
+    resampled
+}
+
+// Position of render point `i` of `render_points`, in solver grid points
+fn position(i: usize, render_points: usize, num_points: usize) -> f32 {
+    if render_points < 2 {
+        return (num_points - 1) as f32 / 2.0;
+    }
+
+    i as f32 * (num_points - 1) as f32 / (render_points - 1) as f32
+}
+
+fn bilinear(values: &[f32], settings: &ResampleSettings, u: f32, v: f32) -> f32 {
+    let num_points_x = settings.num_points_x as usize;
+    let num_points_y = settings.num_points_y as usize;
+
+    let x0 = (u.floor() as usize).min(num_points_x - 1);
+    let y0 = (v.floor() as usize).min(num_points_y - 1);
+    let x1 = (x0 + 1).min(num_points_x - 1);
+    let y1 = (y0 + 1).min(num_points_y - 1);
+    let fx = u - x0 as f32;
+    let fy = v - y0 as f32;
+
+    let value = |x: usize, y: usize| values[x + y * num_points_x];
+    let bottom = value(x0, y0) * (1.0 - fx) + value(x1, y0) * fx;
+    let top = value(x0, y1) * (1.0 - fx) + value(x1, y1) * fx;
+    bottom * (1.0 - fy) + top * fy
+}
+
+fn max_pool(values: &[f32], settings: &ResampleSettings, u: f32, v: f32) -> f32 {
+    let num_points_x = settings.num_points_x as usize;
+    let (x0, x1) = footprint(u, num_points_x, settings.render_points_x as usize);
+    let (y0, y1) = footprint(
+        v,
+        settings.num_points_y as usize,
+        settings.render_points_y as usize,
+    );
+
+    let mut max = f32::NEG_INFINITY;
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            max = max.max(values[x + y * num_points_x]);
+        }
+    }
+    max
+}
+
+// The range of solver points closer to this render point than to its neighbours
+fn footprint(position: f32, num_points: usize, render_points: usize) -> (usize, usize) {
+    let half_width = (num_points - 1) as f32 / (render_points.max(2) - 1) as f32 / 2.0;
+    let last = (num_points - 1) as f32;
+
+    let lower = (position - half_width).ceil().clamp(0.0, last);
+    let upper = (position + half_width).floor().clamp(0.0, last);
+    if lower > upper {
+        let nearest = position.round().clamp(0.0, last) as usize;
+        return (nearest, nearest);
+    }
+
+    (lower as usize, upper as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(
+        num_points: usize,
+        render_points: usize,
+        resampling: Resampling,
+    ) -> ResampleSettings {
+        ResampleSettings::new(
+            num_points,
+            num_points,
+            render_points,
+            render_points,
+            resampling,
+        )
+    }
+
+    #[test]
+    fn keeps_constant_fields() {
+        let values = vec![0.25; 30 * 30];
+        for resampling in [Resampling::Bilinear, Resampling::MaxPool] {
+            for render_points in [1, 7, 30, 61] {
+                let resampled = resample(&values, &settings(30, render_points, resampling));
+                assert_eq!(resampled.len(), render_points * render_points);
+                assert!(resampled.iter().all(|value| *value == 0.25));
+            }
+        }
+    }
+
+    #[test]
+    fn max_pool_keeps_a_spike() {
+        // A single point between render points, which bilinear sampling steps over
+        let mut values = vec![0.0; 31 * 31];
+        values[13 + 17 * 31] = 1.0;
+
+        let max_pool = resample(&values, &settings(31, 11, Resampling::MaxPool));
+        assert_eq!(max_pool.iter().filter(|value| **value == 1.0).count(), 1);
+        assert_eq!(max_pool[4 + 6 * 11], 1.0);
+
+        let bilinear = resample(&values, &settings(31, 11, Resampling::Bilinear));
+        assert!(bilinear.iter().all(|value| *value < 1.0));
+    }
+
+    #[test]
+    fn footprints_stay_on_the_grid() {
+        // Edge render points only cover the half of their footprint inside the grid
+        assert_eq!(footprint(0.0, 31, 11), (0, 1));
+        assert_eq!(footprint(30.0, 31, 11), (29, 30));
+        assert_eq!(footprint(15.0, 31, 11), (14, 16));
+
+        // Upsampling falls back to the nearest solver point
+        assert_eq!(footprint(0.0, 10, 40), (0, 0));
+        assert_eq!(footprint(9.0, 10, 40), (9, 9));
+        assert_eq!(footprint(4.4, 10, 40), (4, 4));
+
+        // A single render point covers the whole grid
+        assert_eq!(footprint(4.5, 10, 1), (0, 9));
+    }
+}
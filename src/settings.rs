@@ -0,0 +1,173 @@
+use crate::{Boundary, Complex, Integrator, Real, SimulationSettings, UnitSystem};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct Settings<T = f32> {
+    pub dth_2mi: Complex<T>, // dt * h_bar / (2 * m * i)
+    pub dx2: T,              // dx ^ 2
+    pub dy2: T,              // dy ^ 2
+    pub dt_hi: Complex<T>,   // dt / (h_bar * i)
+    pub num_points_x: u32,
+    pub num_points_y: u32,
+    pub integrator: u32,
+    pub boundary_x: u32,
+    pub boundary_y: u32,
+    pub potential_scale: T, // converts potentials into hartrees
+}
+
+pub(crate) const FORWARD_EULER: u32 = 0;
+pub(crate) const LEAPFROG: u32 = 1;
+pub(crate) const CRANK_NICOLSON: u32 = 2;
+pub(crate) const SPLIT_STEP: u32 = 3;
+
+pub(crate) const NEUMANN: u32 = 0;
+pub(crate) const DIRICHLET: u32 = 1;
+pub(crate) const PERIODIC: u32 = 2;
+
+// SI values of the atomic units, CODATA 2018
+pub(crate) const H_BAR: f64 = 1.054571817e-34;
+const BOHR_RADIUS: f64 = 5.29177210903e-11;
+const ATOMIC_TIME: f64 = 2.4188843265857e-17;
+const ELECTRON_MASS: f64 = 9.1093837015e-31;
+const HARTREE: f64 = 4.3597447222071e-18;
+
+// Length, time, mass and energy of one user unit in atomic units
+struct Scale {
+    length: f64,
+    time: f64,
+    mass: f64,
+    energy: f64,
+}
+
+impl<T: Real> Settings<T> {
+    // The solver works in atomic units, where h_bar = 1. Converting in double precision keeps the
+    // constants near unity instead of multiplying tiny SI values together in single precision.
+    pub fn new(settings: &SimulationSettings) -> Self {
+        let scale = Scale::new(settings.units());
+        let dx = settings.dx() as f64 * scale.length;
+        let dy = settings.dy() as f64 * scale.length;
+        let dt = settings.dt() as f64 * scale.time;
+        let mass = settings.mass() as f64 * scale.mass;
+
+        Settings {
+            dth_2mi: Complex::new(T::ZERO, T::from_f64(-dt / (2.0 * mass))),
+            dx2: T::from_f64(dx * dx),
+            dy2: T::from_f64(dy * dy),
+            dt_hi: Complex::new(T::ZERO, T::from_f64(-dt)),
+            num_points_x: settings.num_points_x() as u32,
+            num_points_y: settings.num_points_y() as u32,
+            integrator: match settings.integrator() {
+                Integrator::ForwardEuler => FORWARD_EULER,
+                Integrator::Leapfrog => LEAPFROG,
+                Integrator::CrankNicolson => CRANK_NICOLSON,
+                Integrator::SplitStep => SPLIT_STEP,
+            },
+            boundary_x: boundary(settings.boundary_x()),
+            boundary_y: boundary(settings.boundary_y()),
+            potential_scale: T::from_f64(scale.energy),
+        }
+    }
+
+    // The first step has no previous wave to leap from, so it is always taken with forward Euler
+    pub fn startup(self) -> Self {
+        Settings {
+            integrator: FORWARD_EULER,
+            ..self
+        }
+    }
+
+    // Forward Euler in imaginary time t = -i tau, which turns the kinetic term into a real diffusion
+    // step. The step is shortened if needed so that no mode of the discrete Laplacian overshoots.
+    pub fn imaginary_time(self) -> Self {
+        let diffusion =
+            self.dth_2mi.norm() * T::from_f32(4.0) * (T::ONE / self.dx2 + T::ONE / self.dy2);
+        let scale = if diffusion > T::ONE {
+            T::ONE / diffusion
+        } else {
+            T::ONE
+        };
+
+        let rotation = Complex::new(T::ZERO, -scale);
+        Settings {
+            dth_2mi: self.dth_2mi * rotation,
+            dt_hi: self.dt_hi * rotation,
+            integrator: FORWARD_EULER,
+            ..self
+        }
+    }
+}
+
+impl Scale {
+    pub fn new(units: UnitSystem) -> Self {
+        match units {
+            UnitSystem::Si => Scale {
+                length: 1.0 / BOHR_RADIUS,
+                time: 1.0 / ATOMIC_TIME,
+                mass: 1.0 / ELECTRON_MASS,
+                energy: 1.0 / HARTREE,
+            },
+            UnitSystem::Atomic | UnitSystem::Dimensionless => Scale {
+                length: 1.0,
+                time: 1.0,
+                mass: 1.0,
+                energy: 1.0,
+            },
+        }
+    }
+}
+
+fn boundary(boundary: Boundary) -> u32 {
+    match boundary {
+        Boundary::Neumann => NEUMANN,
+        Boundary::Dirichlet => DIRICHLET,
+        Boundary::Periodic => PERIODIC,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::TestSimulation, Eigensolver};
+
+    #[test]
+    fn si_and_atomic_units_agree() {
+        // The test trap in atomic units, then again with every input given in SI
+        let atomic = TestSimulation {
+            settings: TestSimulation::trap()
+                .settings
+                .with_units(UnitSystem::Atomic),
+            ..TestSimulation::trap()
+        };
+        let si = TestSimulation {
+            settings: SimulationSettings::new(
+                40,
+                40,
+                (0.3 * BOHR_RADIUS) as f32,
+                (0.3 * BOHR_RADIUS) as f32,
+                (0.02 * ATOMIC_TIME) as f32,
+                ELECTRON_MASS as f32,
+            )
+            .with_units(UnitSystem::Si),
+            potential: |x, y, _| {
+                let x = x as f64 / BOHR_RADIUS;
+                let y = y as f64 / BOHR_RADIUS;
+                (0.5 * (x * x + y * y) * HARTREE) as f32
+            },
+            ..TestSimulation::trap()
+        };
+
+        // Energies come back in the simulation's own units
+        let atomic = Eigensolver::new(&atomic).unwrap().solve(3).unwrap();
+        let si = Eigensolver::new(&si).unwrap().solve(3).unwrap();
+        for (atomic, si) in atomic.iter().zip(&si) {
+            let si = si.energy() / HARTREE;
+            let difference = (atomic.energy() - si).abs();
+            assert!(
+                difference < 1e-5 * atomic.energy(),
+                "{} != {}",
+                si,
+                atomic.energy()
+            );
+        }
+    }
+}
@@ -30,7 +30,11 @@ float4 f_to_color(float f) {
 PixelInputType vertex_main(VertexInputType input) {
     PixelInputType output;
 
-    float y = wave.GatherRed(sampler_type, input.uv);
+    // The texture holds one texel per mesh vertex
+    uint width, height;
+    wave.GetDimensions(width, height);
+    int2 texel = int2(round(input.uv * float2(width - 1, height - 1)));
+    float y = wave.Load(int3(texel, 0));
 
     output.position = float4(input.position.x, y, input.position.zw);
 
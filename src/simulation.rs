@@ -1,3 +1,6 @@
+use crate::{settings::H_BAR, Complex};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct SimulationSettings {
     num_points_x: usize,
     num_points_y: usize,
@@ -5,6 +8,124 @@ pub struct SimulationSettings {
     dy: f32,
     dt: f32,
     mass: f32,
+    backend: Backend,
+    integrator: Integrator,
+    boundary_x: Boundary,
+    boundary_y: Boundary,
+    potential_update_interval: Option<usize>,
+    absorbing_layer: Option<AbsorbingLayer>,
+    units: UnitSystem,
+    normalize: bool,
+    diagnostics: Option<Diagnostics>,
+    recording: Option<Recording>,
+    checkpointing: Option<Checkpointing>,
+    snapshots: Option<Snapshots>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backend {
+    Gpu,
+    Cpu,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Integrator {
+    // psi_{n+1} = psi_n + dt * H psi_n / (i * h_bar), unstable for any dt
+    ForwardEuler,
+    // psi_{n+1} = psi_{n-1} + 2 * dt * H psi_n / (i * h_bar), stable for small enough dt
+    Leapfrog,
+    // (1 + i * dt * H / (2 * h_bar)) psi_{n+1} = (1 - i * dt * H / (2 * h_bar)) psi_n, stable for
+    // any dt, solved with ADI splitting along x and y. CPU only.
+    CrankNicolson,
+    // Split-operator Fourier method with a spectral kinetic term. CPU only, and without hard walls.
+    SplitStep,
+}
+
+// The system of units that `dx`, `dy`, `dt`, `mass`, positions, times and potentials are given in.
+// The solver converts everything to atomic units internally.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnitSystem {
+    // Metres, seconds, kilograms and joules
+    Si,
+    // Hartree atomic units: bohr radii, h_bar / hartree, electron masses and hartrees
+    Atomic,
+    // h_bar = 1, with mass, length and time in whatever scale the simulation picks
+    Dimensionless,
+}
+
+// What lies beyond the edge of the grid along an axis
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Boundary {
+    // A hard wall, psi = 0 one grid spacing past the edge
+    Dirichlet,
+    // A reflecting wall with zero gradient, psi past the edge equals psi at the edge
+    Neumann,
+    // The grid wraps around to the opposite edge
+    Periodic,
+}
+
+// A complex absorbing potential -i * W along every non-periodic edge of the grid, where W rises
+// quadratically from zero at `width` points in from the edge to `strength` on the edge itself.
+// `strength` is in the same units as the potential.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AbsorbingLayer {
+    width: usize,
+    strength: f32,
+}
+
+// Measures the norm and the energy every `interval` steps, optionally printing each measurement.
+// A tolerance bounds how far a quantity may drift from its initial value, relative to that value
+// (or absolute when it starts at zero), before `action` is taken.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Diagnostics {
+    interval: usize,
+    logging: bool,
+    norm_tolerance: Option<f32>,
+    energy_tolerance: Option<f32>,
+    action: DriftAction,
+}
+
+// What happens when a measurement drifts past its tolerance
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DriftAction {
+    // Prints a warning and keeps stepping
+    Warn,
+    // Stops stepping, `Solver::step` returning `Error::Drift`
+    Abort,
+}
+
+// Appends a row of the step, time, norm, energy and observables to the file at `path` every
+// `interval` steps, starting with the initial state. The file is replaced when a run starts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Recording {
+    path: &'static str,
+    interval: usize,
+    format: RecordFormat,
+}
+
+// Writes a checkpoint of the full solver state to the file at `path` every `interval` steps,
+// replacing the previous one. With `resume`, a run starts from the checkpoint if the file exists.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Checkpointing {
+    path: &'static str,
+    interval: usize,
+    resume: bool,
+}
+
+// Writes psi and its density as NumPy `.npy` files into `directory` every `interval` steps, starting
+// with the initial state. Each pair comes with a JSON file holding the step, time and grid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Snapshots {
+    directory: &'static str,
+    interval: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordFormat {
+    // Comma separated values with a header row naming the columns
+    Csv,
+    // One JSON object per line, keyed by the same names as the CSV columns
+    JsonLines,
 }
 
 pub struct RenderSettings {
@@ -13,6 +134,18 @@ pub struct RenderSettings {
 
     y_scale: f32,
     xz_scale: f32,
+
+    resampling: Resampling,
+}
+
+// How the density on the solver grid is mapped onto the render mesh
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Resampling {
+    // Interpolates between the four nearest solver points
+    Bilinear,
+    // Takes the largest value among the solver points a render point covers, so narrow peaks stay
+    // visible when the render mesh is coarser than the solver grid
+    MaxPool,
 }
 
 pub trait Simulation {
@@ -22,7 +155,17 @@ pub trait Simulation {
     fn render_settings(&self) -> RenderSettings;
     fn time_scale(&self) -> f32;
 
-    fn psi_0(&self, x: f32, y: f32) -> (f32, f32);
+    fn psi_0(&self, x: f32, y: f32) -> Complex;
+
+    // In the simulation's energy units. An infinite value is a hard wall that psi can't enter.
+    fn potential(&self, _x: f32, _y: f32) -> f32 {
+        0.0
+    }
+
+    // Sampled at startup and then again every `potential_update_interval` steps
+    fn time_dependent_potential(&self, x: f32, y: f32, _t: f32) -> f32 {
+        self.potential(x, y)
+    }
 }
 
 impl SimulationSettings {
@@ -41,6 +184,85 @@ impl SimulationSettings {
             dy,
             dt,
             mass,
+            backend: Backend::Gpu,
+            integrator: Integrator::ForwardEuler,
+            boundary_x: Boundary::Neumann,
+            boundary_y: Boundary::Neumann,
+            potential_update_interval: None,
+            absorbing_layer: None,
+            units: UnitSystem::Si,
+            normalize: false,
+            diagnostics: None,
+            recording: None,
+            checkpointing: None,
+            snapshots: None,
+        }
+    }
+
+    pub const fn with_backend(self, backend: Backend) -> Self {
+        SimulationSettings { backend, ..self }
+    }
+
+    pub const fn with_integrator(self, integrator: Integrator) -> Self {
+        SimulationSettings { integrator, ..self }
+    }
+
+    pub const fn with_boundaries(self, boundary_x: Boundary, boundary_y: Boundary) -> Self {
+        SimulationSettings {
+            boundary_x,
+            boundary_y,
+            ..self
+        }
+    }
+
+    pub const fn with_potential_update_interval(self, steps: usize) -> Self {
+        SimulationSettings {
+            potential_update_interval: Some(steps),
+            ..self
+        }
+    }
+
+    pub const fn with_absorbing_layer(self, absorbing_layer: AbsorbingLayer) -> Self {
+        SimulationSettings {
+            absorbing_layer: Some(absorbing_layer),
+            ..self
+        }
+    }
+
+    pub const fn with_units(self, units: UnitSystem) -> Self {
+        SimulationSettings { units, ..self }
+    }
+
+    // Rescales `psi_0` to unit norm before the first step
+    pub const fn with_normalization(self, normalize: bool) -> Self {
+        SimulationSettings { normalize, ..self }
+    }
+
+    pub const fn with_diagnostics(self, diagnostics: Diagnostics) -> Self {
+        SimulationSettings {
+            diagnostics: Some(diagnostics),
+            ..self
+        }
+    }
+
+    pub const fn with_recording(self, recording: Recording) -> Self {
+        SimulationSettings {
+            recording: Some(recording),
+            ..self
+        }
+    }
+
+    pub const fn with_checkpointing(self, checkpointing: Checkpointing) -> Self {
+        SimulationSettings {
+            checkpointing: Some(checkpointing),
+            ..self
+        }
+    }
+
+    pub const fn with_snapshots(self, snapshots: Snapshots) -> Self {
+        SimulationSettings {
+            snapshots: Some(snapshots),
+            ..self
         }
     }
 
@@ -67,6 +289,205 @@ impl SimulationSettings {
     pub fn mass(&self) -> f32 {
         self.mass
     }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    pub fn boundary_x(&self) -> Boundary {
+        self.boundary_x
+    }
+
+    pub fn boundary_y(&self) -> Boundary {
+        self.boundary_y
+    }
+
+    pub fn potential_update_interval(&self) -> Option<usize> {
+        self.potential_update_interval
+    }
+
+    pub fn absorbing_layer(&self) -> Option<AbsorbingLayer> {
+        self.absorbing_layer
+    }
+
+    pub fn units(&self) -> UnitSystem {
+        self.units
+    }
+
+    pub fn normalize(&self) -> bool {
+        self.normalize
+    }
+
+    pub fn diagnostics(&self) -> Option<Diagnostics> {
+        self.diagnostics
+    }
+
+    pub fn recording(&self) -> Option<Recording> {
+        self.recording
+    }
+
+    pub fn checkpointing(&self) -> Option<Checkpointing> {
+        self.checkpointing
+    }
+
+    pub fn snapshots(&self) -> Option<Snapshots> {
+        self.snapshots
+    }
+}
+
+impl UnitSystem {
+    // h_bar in these units
+    pub fn h_bar(self) -> f64 {
+        match self {
+            UnitSystem::Si => H_BAR,
+            UnitSystem::Atomic | UnitSystem::Dimensionless => 1.0,
+        }
+    }
+}
+
+impl AbsorbingLayer {
+    pub const fn new(width: usize, strength: f32) -> Self {
+        AbsorbingLayer { width, strength }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn strength(&self) -> f32 {
+        self.strength
+    }
+}
+
+impl Diagnostics {
+    pub const fn new(interval: usize) -> Self {
+        Diagnostics {
+            interval,
+            logging: false,
+            norm_tolerance: None,
+            energy_tolerance: None,
+            action: DriftAction::Warn,
+        }
+    }
+
+    pub const fn with_logging(self, logging: bool) -> Self {
+        Diagnostics { logging, ..self }
+    }
+
+    pub const fn with_norm_tolerance(self, tolerance: f32) -> Self {
+        Diagnostics {
+            norm_tolerance: Some(tolerance),
+            ..self
+        }
+    }
+
+    pub const fn with_energy_tolerance(self, tolerance: f32) -> Self {
+        Diagnostics {
+            energy_tolerance: Some(tolerance),
+            ..self
+        }
+    }
+
+    pub const fn with_action(self, action: DriftAction) -> Self {
+        Diagnostics { action, ..self }
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    pub fn logging(&self) -> bool {
+        self.logging
+    }
+
+    pub fn norm_tolerance(&self) -> Option<f32> {
+        self.norm_tolerance
+    }
+
+    pub fn energy_tolerance(&self) -> Option<f32> {
+        self.energy_tolerance
+    }
+
+    pub fn action(&self) -> DriftAction {
+        self.action
+    }
+}
+
+impl Recording {
+    pub const fn new(path: &'static str, interval: usize, format: RecordFormat) -> Self {
+        Recording {
+            path,
+            interval,
+            format,
+        }
+    }
+
+    pub const fn csv(path: &'static str, interval: usize) -> Self {
+        Recording::new(path, interval, RecordFormat::Csv)
+    }
+
+    pub const fn json_lines(path: &'static str, interval: usize) -> Self {
+        Recording::new(path, interval, RecordFormat::JsonLines)
+    }
+
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    pub fn format(&self) -> RecordFormat {
+        self.format
+    }
+}
+
+impl Checkpointing {
+    pub const fn new(path: &'static str, interval: usize) -> Self {
+        Checkpointing {
+            path,
+            interval,
+            resume: false,
+        }
+    }
+
+    pub const fn with_resume(self, resume: bool) -> Self {
+        Checkpointing { resume, ..self }
+    }
+
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    pub fn resume(&self) -> bool {
+        self.resume
+    }
+}
+
+impl Snapshots {
+    pub const fn new(directory: &'static str, interval: usize) -> Self {
+        Snapshots {
+            directory,
+            interval,
+        }
+    }
+
+    pub fn directory(&self) -> &'static str {
+        self.directory
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
 }
 
 impl RenderSettings {
@@ -81,9 +502,14 @@ impl RenderSettings {
             num_points_y,
             y_scale,
             xz_scale,
+            resampling: Resampling::Bilinear,
         }
     }
 
+    pub const fn with_resampling(self, resampling: Resampling) -> Self {
+        RenderSettings { resampling, ..self }
+    }
+
     pub fn num_points_x(&self) -> usize {
         self.num_points_x
     }
@@ -99,4 +525,23 @@ impl RenderSettings {
     pub fn xz_scale(&self) -> f32 {
         self.xz_scale
     }
+
+    pub fn resampling(&self) -> Resampling {
+        self.resampling
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_euler_is_the_default() {
+        let settings = SimulationSettings::new(8, 8, 1.0, 1.0, 0.1, 1.0);
+        assert_eq!(settings.integrator(), Integrator::ForwardEuler);
+        assert_eq!(
+            settings.with_integrator(Integrator::Leapfrog).integrator(),
+            Integrator::Leapfrog
+        );
+    }
 }
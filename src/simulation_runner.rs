@@ -1,84 +1,106 @@
-use crate::Simulation;
+use crate::{
+    boundary, checkpoint::Header, current_wave::CurrentWave, diagnostics, observables::NUM_SUMS,
+    settings::Settings, solver_backend::sample_psi_0, Backend, Checkpoint, Complex, Density, Error,
+    Grid, Integrator, Measurement, Observables, Simulation, SolverBackend,
+};
 use colosseum::{Input, Window};
-use std::f32::consts::PI;
-
-enum CurrentWave {
-    Wave1,
-    Wave2,
-    Wave3,
-}
-
-#[repr(C)]
-struct Settings {
-    dth_2mi: (f32, f32), // dt * h_bar / (2 * m * i)
-    dx2: f32,            // dx ^ 2
-    dy2: f32,            // dy ^ 2
-    num_points_x: u32,
-    num_points_y: u32,
-    reserved: (f32, f32),
-}
 
 pub struct SimulationRunner {
     num_thread_groups_x: usize,
     num_thread_groups_y: usize,
-    width: f32,
-    height: f32,
-    dx: f32,
-    dy: f32,
+    grid: Grid,
     dt: f32,
+    h_bar: f64,
+    header: Header,
+    steps: usize,
+    initial_norm: f32,
     current_wave: CurrentWave,
+    startup: bool,
 
     // Wave buffers
-    wave1: alexandria::compute::Buffer<(f32, f32)>,
-    wave2: alexandria::compute::Buffer<(f32, f32)>,
-    wave3: alexandria::compute::Buffer<(f32, f32)>,
+    wave1: alexandria::compute::Buffer<Complex>,
+    wave2: alexandria::compute::Buffer<Complex>,
+    wave3: alexandria::compute::Buffer<Complex>,
 
     output: alexandria::Texture,
 
+    potential: alexandria::compute::Buffer<f32>,
+    potential_values: Vec<f32>,
+    damping: alexandria::compute::Buffer<f32>,
+    absorption: Vec<f32>,
+
     // Constant buffer
+    settings: Settings,
     settings_buffer: alexandria::ConstantBuffer<Settings>,
 
     // Compute shader
     compute_shader: alexandria::compute::ComputeShader,
+
+    // Reduction of the observables, NUM_SUMS partial sums per thread group
+    observables_shader: alexandria::compute::ComputeShader,
+    sums: alexandria::compute::Buffer<f32>,
 }
 
 const PREVIOUS_WAVE_SLOT: usize = 0;
 const CURRENT_WAVE_SLOT: usize = 1;
 const NEXT_WAVE_SLOT: usize = 2;
 const OUTPUT_SLOT: usize = 3;
+const POTENTIAL_SLOT: usize = 4;
+const DAMPING_SLOT: usize = 5;
+const SUMS_SLOT: usize = 6;
 
-const H: f32 = 6.62607015e-34;
-const H_BAR: f32 = H / (2.0 * PI);
+// Matches `numthreads` in `compute.hlsl`
+const THREAD_GROUP_SIZE: usize = 16;
+
+// The largest texture dimension D3D11 supports
+const MAX_POINTS: usize = 16384;
 
 impl SimulationRunner {
-    pub fn new<I: Input, S: Simulation>(simulation: &S, window: &mut Window<I>) -> Self {
+    pub fn new<I: Input, S: Simulation>(
+        simulation: &S,
+        window: &mut Window<I>,
+    ) -> Result<Self, Error> {
         let settings = simulation.simulation_settings();
 
-        assert_eq!(settings.num_points_x() % 16, 0);
-        assert_eq!(settings.num_points_y() % 16, 0);
+        if settings.num_points_x() > MAX_POINTS || settings.num_points_y() > MAX_POINTS {
+            return Err(Error::InvalidGridSize {
+                num_points_x: settings.num_points_x(),
+                num_points_y: settings.num_points_y(),
+            });
+        }
+        if matches!(
+            settings.integrator(),
+            Integrator::CrankNicolson | Integrator::SplitStep
+        ) {
+            return Err(Error::UnsupportedIntegrator {
+                integrator: settings.integrator(),
+                backend: Backend::Gpu,
+            });
+        }
 
-        let width = ((settings.num_points_x() - 1) as f32) * settings.dx();
-        let height = ((settings.num_points_y() - 1) as f32) * settings.dy();
+        let grid = Grid::new(&settings)?;
 
         let shader_code = include_str!("compute.hlsl");
         let compute_shader =
             alexandria::compute::ComputeShader::new(shader_code, window.inner()).unwrap();
 
-        let mut wave_values = Vec::with_capacity(settings.num_points_x() * settings.num_points_y());
-        let mut output_values =
-            Vec::with_capacity(settings.num_points_x() * settings.num_points_y());
-        let base_x = -(width / 2.0);
-        let base_y = -(height / 2.0);
-        for y in 0..settings.num_points_y() {
-            for x in 0..settings.num_points_x() {
-                let x = base_x + x as f32 * settings.dx();
-                let y = base_y + y as f32 * settings.dy();
-
-                let psi = simulation.psi_0(x, y);
-                wave_values.push(psi);
-                output_values.push(psi.0 * psi.0 + psi.1 * psi.1);
-            }
-        }
+        let observables_shader = alexandria::compute::ComputeShader::new(
+            include_str!("observables.hlsl"),
+            window.inner(),
+        )
+        .unwrap();
+
+        let num_thread_groups_x = settings.num_points_x().div_ceil(THREAD_GROUP_SIZE);
+        let num_thread_groups_y = settings.num_points_y().div_ceil(THREAD_GROUP_SIZE);
+        let sums = alexandria::compute::Buffer::new(
+            &vec![0.0; num_thread_groups_x * num_thread_groups_y * NUM_SUMS],
+            SUMS_SLOT,
+            window.inner(),
+        )
+        .unwrap();
+
+        let (wave_values, initial_norm) = sample_psi_0(&grid, simulation);
+        let output_values: Vec<f32> = wave_values.iter().map(|psi| psi.norm_sqr()).collect();
 
         let wave1 =
             alexandria::compute::Buffer::new(&wave_values, CURRENT_WAVE_SLOT, window.inner())
@@ -96,64 +118,95 @@ impl SimulationRunner {
             window.inner(),
         );
 
-        let settings_buffer = Settings {
-            dth_2mi: (0.0, -settings.dt() * H_BAR / (2.0 * settings.mass())),
-            dx2: settings.dx() * settings.dx(),
-            dy2: settings.dy() * settings.dy(),
-            num_points_x: settings.num_points_x() as u32,
-            num_points_y: settings.num_points_y() as u32,
-            reserved: (0.0, 0.0),
-        };
+        let settings_values = Settings::new(&settings);
+
+        let mut potential_values =
+            grid.sample(|x, y| simulation.time_dependent_potential(x, y, 0.0));
+        let absorption = boundary::damping(settings.absorbing_layer(), &settings_values);
+        let mut damping_values = absorption.clone();
+        boundary::walls(&mut potential_values, &absorption, &mut damping_values);
+
+        let potential =
+            alexandria::compute::Buffer::new(&potential_values, POTENTIAL_SLOT, window.inner())
+                .unwrap();
+        let damping =
+            alexandria::compute::Buffer::new(&damping_values, DAMPING_SLOT, window.inner())
+                .unwrap();
+
         let settings_buffer =
-            alexandria::ConstantBuffer::new(Some(settings_buffer), 0, window.inner()).unwrap();
-
-        SimulationRunner {
-            num_thread_groups_x: settings.num_points_x() / 16,
-            num_thread_groups_y: settings.num_points_y() / 16,
-            width,
-            height,
-            dx: settings.dx(),
-            dy: settings.dy(),
+            alexandria::ConstantBuffer::new(Some(settings_values.startup()), 0, window.inner())
+                .unwrap();
+
+        Ok(SimulationRunner {
+            num_thread_groups_x,
+            num_thread_groups_y,
+            grid,
             dt: settings.dt(),
+            h_bar: settings.units().h_bar(),
+            header: Header::new(&settings),
+            steps: 0,
+            initial_norm,
             current_wave: CurrentWave::Wave1,
+            startup: true,
             compute_shader,
             wave1,
             wave2,
             wave3,
             output,
+            potential,
+            potential_values,
+            damping,
+            absorption,
+            settings: settings_values,
             settings_buffer,
-        }
+            observables_shader,
+            sums,
+        })
     }
 
-    pub fn num_points_x(&self) -> usize {
-        self.num_thread_groups_x * 16
+    fn current_wave(&mut self) -> &mut alexandria::compute::Buffer<Complex> {
+        match self.current_wave {
+            CurrentWave::Wave1 => &mut self.wave1,
+            CurrentWave::Wave2 => &mut self.wave2,
+            CurrentWave::Wave3 => &mut self.wave3,
+        }
     }
 
-    pub fn num_points_y(&self) -> usize {
-        self.num_thread_groups_y * 16
+    fn next_wave(&mut self) -> &mut alexandria::compute::Buffer<Complex> {
+        match self.current_wave {
+            CurrentWave::Wave1 => &mut self.wave2,
+            CurrentWave::Wave2 => &mut self.wave3,
+            CurrentWave::Wave3 => &mut self.wave1,
+        }
     }
 
-    pub fn width(&self) -> f32 {
-        self.width
+    fn previous_wave(&mut self) -> &mut alexandria::compute::Buffer<Complex> {
+        match self.current_wave {
+            CurrentWave::Wave1 => &mut self.wave3,
+            CurrentWave::Wave2 => &mut self.wave1,
+            CurrentWave::Wave3 => &mut self.wave2,
+        }
     }
+}
 
-    pub fn height(&self) -> f32 {
-        self.height
+impl<I: Input> SolverBackend<Window<I>> for SimulationRunner {
+    fn grid(&self) -> &Grid {
+        &self.grid
     }
 
-    pub fn dx(&self) -> f32 {
-        self.dx
+    fn dt(&self) -> f32 {
+        self.dt
     }
 
-    pub fn dy(&self) -> f32 {
-        self.dy
+    fn steps(&self) -> usize {
+        self.steps
     }
 
-    pub fn dt(&self) -> f32 {
-        self.dt
+    fn initial_norm(&self) -> f32 {
+        self.initial_norm
     }
 
-    pub fn update<I: Input>(&mut self, window: &mut Window<I>) {
+    fn step(&mut self, window: &mut Window<I>) {
         self.previous_wave().set_slot(PREVIOUS_WAVE_SLOT);
         self.current_wave().set_slot(CURRENT_WAVE_SLOT);
         self.next_wave().set_slot(NEXT_WAVE_SLOT);
@@ -163,6 +216,8 @@ impl SimulationRunner {
         self.current_wave().set_active_rw(window.inner());
         self.next_wave().set_active_rw(window.inner());
         self.output.set_active_compute_rw(window.inner());
+        self.potential.set_active_rw(window.inner());
+        self.damping.set_active_rw(window.inner());
         self.settings_buffer.set_active_compute(window.inner());
 
         self.compute_shader.dispatch(
@@ -172,42 +227,160 @@ impl SimulationRunner {
             window.inner(),
         );
 
-        self.set_next_wave();
+        self.current_wave = self.current_wave.next();
+        self.steps += 1;
+
+        if self.startup {
+            self.startup = false;
+            self.settings_buffer =
+                alexandria::ConstantBuffer::new(Some(self.settings), 0, window.inner()).unwrap();
+        }
     }
 
-    pub fn output(&mut self) -> &mut alexandria::Texture {
-        &mut self.output
+    fn density(&mut self, _: &mut Window<I>) -> Density<'_> {
+        Density::Texture(&mut self.output)
     }
 
-    fn current_wave(&mut self) -> &mut alexandria::compute::Buffer<(f32, f32)> {
-        match self.current_wave {
-            CurrentWave::Wave1 => &mut self.wave1,
-            CurrentWave::Wave2 => &mut self.wave2,
-            CurrentWave::Wave3 => &mut self.wave3,
-        }
+    fn psi(&mut self, window: &mut Window<I>) -> Vec<Complex> {
+        window.inner().device_context().flush();
+        self.current_wave().read(window.inner()).unwrap()
     }
 
-    fn next_wave(&mut self) -> &mut alexandria::compute::Buffer<(f32, f32)> {
-        match self.current_wave {
-            CurrentWave::Wave1 => &mut self.wave2,
-            CurrentWave::Wave2 => &mut self.wave3,
-            CurrentWave::Wave3 => &mut self.wave1,
+    fn set_psi(&mut self, psi: &[Complex], window: &mut Window<I>) -> Result<(), Error> {
+        self.grid.check_size(psi.len())?;
+        let output_values: Vec<f32> = psi.iter().map(|psi| psi.norm_sqr()).collect();
+
+        self.wave1 =
+            alexandria::compute::Buffer::new(psi, CURRENT_WAVE_SLOT, window.inner()).unwrap();
+        self.wave2 = alexandria::compute::Buffer::new(psi, NEXT_WAVE_SLOT, window.inner()).unwrap();
+        self.wave3 =
+            alexandria::compute::Buffer::new(psi, PREVIOUS_WAVE_SLOT, window.inner()).unwrap();
+        self.current_wave = CurrentWave::Wave1;
+
+        self.startup = true;
+        self.settings_buffer =
+            alexandria::ConstantBuffer::new(Some(self.settings.startup()), 0, window.inner())
+                .unwrap();
+
+        self.output = alexandria::Texture::new_1f(
+            &output_values,
+            self.grid.num_points_x(),
+            OUTPUT_SLOT,
+            window.inner(),
+        );
+        Ok(())
+    }
+
+    fn measure(&mut self, window: &mut Window<I>) -> Measurement {
+        let psi = self.psi(window);
+        diagnostics::measure(
+            &self.grid,
+            &self.settings,
+            &psi,
+            &self.potential_values,
+            self.steps,
+            SolverBackend::<Window<I>>::time(self),
+        )
+    }
+
+    fn observables(&mut self, window: &mut Window<I>) -> Observables {
+        self.current_wave().set_slot(CURRENT_WAVE_SLOT);
+
+        self.observables_shader.set_active(window.inner());
+        self.current_wave().set_active_rw(window.inner());
+        self.sums.set_active_rw(window.inner());
+        self.settings_buffer.set_active_compute(window.inner());
+
+        self.observables_shader.dispatch(
+            self.num_thread_groups_x,
+            self.num_thread_groups_y,
+            1,
+            window.inner(),
+        );
+
+        // The partial sums of all thread groups are added up in double precision
+        window.inner().device_context().flush();
+        let partial_sums = self.sums.read(window.inner()).unwrap();
+        let mut sums = [0.0; NUM_SUMS];
+        for group in partial_sums.chunks(NUM_SUMS) {
+            for (sum, partial_sum) in sums.iter_mut().zip(group) {
+                *sum += *partial_sum as f64;
+            }
         }
+
+        Observables::from_sums(
+            sums,
+            &self.grid,
+            self.h_bar,
+            self.steps,
+            SolverBackend::<Window<I>>::time(self),
+        )
     }
 
-    fn previous_wave(&mut self) -> &mut alexandria::compute::Buffer<(f32, f32)> {
-        match self.current_wave {
-            CurrentWave::Wave1 => &mut self.wave3,
-            CurrentWave::Wave2 => &mut self.wave1,
-            CurrentWave::Wave3 => &mut self.wave2,
+    fn set_potential(&mut self, potential: &[f32], window: &mut Window<I>) -> Result<(), Error> {
+        self.grid.check_size(potential.len())?;
+        let mut potential = potential.to_vec();
+        let mut damping = vec![0.0; potential.len()];
+        boundary::walls(&mut potential, &self.absorption, &mut damping);
+
+        self.potential =
+            alexandria::compute::Buffer::new(&potential, POTENTIAL_SLOT, window.inner()).unwrap();
+        self.potential_values = potential;
+        self.damping =
+            alexandria::compute::Buffer::new(&damping, DAMPING_SLOT, window.inner()).unwrap();
+        Ok(())
+    }
+
+    fn checkpoint(&mut self, window: &mut Window<I>) -> Checkpoint {
+        window.inner().device_context().flush();
+        let waves = [
+            self.wave1.read(window.inner()).unwrap(),
+            self.wave2.read(window.inner()).unwrap(),
+            self.wave3.read(window.inner()).unwrap(),
+        ];
+
+        Checkpoint {
+            header: self.header,
+            steps: self.steps,
+            startup: self.startup,
+            initial_norm: self.initial_norm,
+            current_wave: self.current_wave,
+            waves,
         }
     }
 
-    fn set_next_wave(&mut self) {
-        self.current_wave = match self.current_wave {
-            CurrentWave::Wave1 => CurrentWave::Wave2,
-            CurrentWave::Wave2 => CurrentWave::Wave3,
-            CurrentWave::Wave3 => CurrentWave::Wave1,
+    fn restore(&mut self, checkpoint: &Checkpoint, window: &mut Window<I>) -> Result<(), Error> {
+        checkpoint.check(&self.header)?;
+
+        // Slots are reassigned before every dispatch, so any will do here
+        let [wave1, wave2, wave3] = &checkpoint.waves;
+        self.wave1 =
+            alexandria::compute::Buffer::new(wave1, CURRENT_WAVE_SLOT, window.inner()).unwrap();
+        self.wave2 =
+            alexandria::compute::Buffer::new(wave2, NEXT_WAVE_SLOT, window.inner()).unwrap();
+        self.wave3 =
+            alexandria::compute::Buffer::new(wave3, PREVIOUS_WAVE_SLOT, window.inner()).unwrap();
+        self.current_wave = checkpoint.current_wave;
+        self.steps = checkpoint.steps;
+        self.initial_norm = checkpoint.initial_norm;
+
+        self.startup = checkpoint.startup;
+        let settings = if self.startup {
+            self.settings.startup()
+        } else {
+            self.settings
         };
+        self.settings_buffer =
+            alexandria::ConstantBuffer::new(Some(settings), 0, window.inner()).unwrap();
+
+        let output_values: Vec<f32> = checkpoint.psi().iter().map(|psi| psi.norm_sqr()).collect();
+        self.output = alexandria::Texture::new_1f(
+            &output_values,
+            self.grid.num_points_x(),
+            OUTPUT_SLOT,
+            window.inner(),
+        );
+
+        Ok(())
     }
 }
@@ -0,0 +1,203 @@
+use crate::{Error, Real, Snapshots, SolverBackend, UnitSystem};
+use std::io::Write;
+use std::path::Path;
+
+// Writes psi and |psi|^2 as NumPy `.npy` files into `directory`, named after the step count, with a
+// JSON sidecar holding the grid and time. The arrays have shape (num_points_y, num_points_x), so
+// `psi[j, i]` is the point at x = x_min + i * dx, y = y_min + j * dy.
+pub(crate) fn write<C, T: Real, B: SolverBackend<C, T> + ?Sized>(
+    directory: &str,
+    units: UnitSystem,
+    backend: &mut B,
+    context: &mut C,
+) -> Result<(), Error> {
+    let psi = backend.psi(context);
+    let steps = backend.steps();
+    let grid = backend.grid();
+    let shape = (grid.num_points_y(), grid.num_points_x());
+    let double = std::mem::size_of::<T>() == 8;
+
+    let directory = Path::new(directory);
+    std::fs::create_dir_all(directory).map_err(io_error)?;
+
+    let psi_name = format!("psi_{:08}.npy", steps);
+    let density_name = format!("density_{:08}.npy", steps);
+
+    write_npy(
+        &directory.join(&psi_name),
+        if double { "<c16" } else { "<c8" },
+        shape,
+        |file| {
+            for psi in &psi {
+                write_value(file, psi.re, double)?;
+                write_value(file, psi.im, double)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    write_npy(
+        &directory.join(&density_name),
+        if double { "<f8" } else { "<f4" },
+        shape,
+        |file| {
+            for psi in &psi {
+                write_value(file, psi.norm_sqr(), double)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    let sidecar = format!(
+        "{{\"step\":{},\"time\":{:e},\"dt\":{:e},\"dx\":{:e},\"dy\":{:e},\"x_min\":{:e},\"y_min\":{:e},\
+         \"num_points_x\":{},\"num_points_y\":{},\"units\":\"{:?}\",\"psi\":\"{}\",\"density\":\"{}\"}}\n",
+        steps,
+        backend.time(),
+        backend.dt(),
+        grid.dx(),
+        grid.dy(),
+        -grid.width() / 2.0,
+        -grid.height() / 2.0,
+        grid.num_points_x(),
+        grid.num_points_y(),
+        units,
+        psi_name,
+        density_name,
+    );
+    std::fs::write(
+        directory.join(format!("snapshot_{:08}.json", steps)),
+        sidecar,
+    )
+    .map_err(io_error)
+}
+
+// Writes a snapshot if one is due
+pub(crate) fn update<C, T: Real, B: SolverBackend<C, T> + ?Sized>(
+    snapshots: Snapshots,
+    units: UnitSystem,
+    backend: &mut B,
+    context: &mut C,
+) -> Result<(), Error> {
+    let interval = snapshots.interval();
+    if interval == 0 || !backend.steps().is_multiple_of(interval) {
+        return Ok(());
+    }
+
+    write(snapshots.directory(), units, backend, context)
+}
+
+// Version 1.0 of the format: magic, version, header length, then a Python dict literal padded with
+// spaces so the data starts on a 64 byte boundary
+fn write_npy<F: FnOnce(&mut std::io::BufWriter<std::fs::File>) -> std::io::Result<()>>(
+    path: &Path,
+    descr: &str,
+    shape: (usize, usize),
+    write_data: F,
+) -> Result<(), Error> {
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({}, {}), }}",
+        descr, shape.0, shape.1
+    );
+    const PREAMBLE: usize = 10;
+    while !(PREAMBLE + header.len() + 1).is_multiple_of(64) {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    let file = std::fs::File::create(path).map_err(io_error)?;
+    let mut file = std::io::BufWriter::new(file);
+    file.write_all(b"\x93NUMPY\x01\x00")
+        .and_then(|_| file.write_all(&(header.len() as u16).to_le_bytes()))
+        .and_then(|_| file.write_all(header.as_bytes()))
+        .and_then(|_| write_data(&mut file))
+        .and_then(|_| file.flush())
+        .map_err(io_error)
+}
+
+fn write_value<W: Write, T: Real>(file: &mut W, value: T, double: bool) -> std::io::Result<()> {
+    if double {
+        file.write_all(&value.to_f64().to_le_bytes())
+    } else {
+        file.write_all(&value.to_f32().to_le_bytes())
+    }
+}
+
+fn io_error(error: std::io::Error) -> Error {
+    Error::Io { kind: error.kind() }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::TestSimulation, Complex, Simulation, SimulationSettings, Solver};
+
+    // Splits a `.npy` file into its header dict and data, checking the preamble and alignment
+    fn parse(bytes: &[u8]) -> (String, &[u8]) {
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_length) % 64, 0);
+
+        let header = std::str::from_utf8(&bytes[10..10 + header_length]).unwrap();
+        assert!(header.ends_with('\n'));
+        (header.trim_end().to_string(), &bytes[10 + header_length..])
+    }
+
+    #[test]
+    fn writes_npy_files_and_a_sidecar() {
+        let directory = std::env::temp_dir().join(format!("wave-snapshot-{}", std::process::id()));
+        let simulation = TestSimulation {
+            settings: SimulationSettings::new(5, 3, 0.5, 1.0, 0.1, 1.0),
+            psi_0: |x, y| Complex::new(x, y),
+            ..TestSimulation::new()
+        };
+
+        let mut solver = Solver::new(&simulation).unwrap();
+        solver.write_snapshot(directory.to_str().unwrap()).unwrap();
+        let mut double = Solver::<f64>::with_precision(&simulation).unwrap();
+        double.step(2).unwrap();
+        double.write_snapshot(directory.to_str().unwrap()).unwrap();
+
+        let psi = std::fs::read(directory.join("psi_00000000.npy")).unwrap();
+        let (header, data) = parse(&psi);
+        assert_eq!(
+            header,
+            "{'descr': '<c8', 'fortran_order': False, 'shape': (3, 5), }"
+        );
+        assert_eq!(data.len(), 15 * 8);
+
+        // Row by row, x varying fastest from x_min = -1, y_min = -1
+        let value = |i: usize| f32::from_le_bytes(data[4 * i..4 * i + 4].try_into().unwrap());
+        let grid = simulation.simulation_settings();
+        assert_eq!((value(0), value(1)), (-1.0, -1.0));
+        assert_eq!((value(2), value(3)), (-1.0 + grid.dx(), -1.0));
+        assert_eq!((value(10), value(11)), (-1.0, 0.0));
+
+        let density = std::fs::read(directory.join("density_00000000.npy")).unwrap();
+        let (header, data) = parse(&density);
+        assert_eq!(
+            header,
+            "{'descr': '<f4', 'fortran_order': False, 'shape': (3, 5), }"
+        );
+        assert_eq!(f32::from_le_bytes(data[..4].try_into().unwrap()), 2.0);
+
+        let psi = std::fs::read(directory.join("psi_00000002.npy")).unwrap();
+        let (header, data) = parse(&psi);
+        assert_eq!(
+            header,
+            "{'descr': '<c16', 'fortran_order': False, 'shape': (3, 5), }"
+        );
+        assert_eq!(data.len(), 15 * 16);
+
+        let sidecar = std::fs::read_to_string(directory.join("snapshot_00000002.json")).unwrap();
+        assert_eq!(
+            sidecar,
+            format!(
+                "{{\"step\":2,\"time\":{:e},\"dt\":1e-1,\"dx\":5e-1,\"dy\":1e0,\"x_min\":-1e0,\
+                 \"y_min\":-1e0,\"num_points_x\":5,\"num_points_y\":3,\"units\":\"Si\",\
+                 \"psi\":\"psi_00000002.npy\",\"density\":\"density_00000002.npy\"}}\n",
+                double.time()
+            )
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    checkpoint, cpu_simulation_runner::CpuSimulationRunner, recorder::Recorder, snapshot,
+    solver_backend::update_potential, Checkpoint, Checkpointing, Complex, Error, Grid, Measurement,
+    Monitor, Observables, Real, Simulation, Snapshots, SolverBackend, UnitSystem,
+};
+
+// Steps a simulation on the CPU without creating a window, in single or double precision
+pub struct Solver<T: Real = f32> {
+    simulation_runner: CpuSimulationRunner<T>,
+    potential_update_interval: Option<usize>,
+    monitor: Option<Monitor<T>>,
+    recorder: Option<Recorder>,
+    checkpointing: Option<Checkpointing>,
+    snapshots: Option<Snapshots>,
+    units: UnitSystem,
+}
+
+impl Solver {
+    pub fn new<S: Simulation>(simulation: &S) -> Result<Self, Error> {
+        Solver::with_precision(simulation)
+    }
+}
+
+impl<T: Real> Solver<T> {
+    // Creates a solver computing in `T`, e.g. `Solver::<f64>::with_precision(&simulation)`
+    pub fn with_precision<S: Simulation>(simulation: &S) -> Result<Self, Error> {
+        let settings = simulation.simulation_settings();
+        let mut simulation_runner = CpuSimulationRunner::new(simulation)?;
+        let resumed = match settings.checkpointing() {
+            Some(checkpointing) => {
+                checkpoint::resume(checkpointing, simulation, &mut simulation_runner, &mut ())?
+            }
+            None => false,
+        };
+
+        let monitor = settings
+            .diagnostics()
+            .map(|diagnostics| Monitor::new(diagnostics, simulation_runner.measure(&mut ())));
+        let recorder = settings
+            .recording()
+            .map(|recording| Recorder::new(recording, resumed, &mut simulation_runner, &mut ()))
+            .transpose()?;
+        if let Some(snapshots) = settings.snapshots() {
+            snapshot::write(
+                snapshots.directory(),
+                settings.units(),
+                &mut simulation_runner,
+                &mut (),
+            )?;
+        }
+
+        Ok(Solver {
+            simulation_runner,
+            potential_update_interval: settings.potential_update_interval(),
+            monitor,
+            recorder,
+            checkpointing: settings.checkpointing(),
+            snapshots: settings.snapshots(),
+            units: settings.units(),
+        })
+    }
+
+    pub fn grid(&self) -> &Grid {
+        SolverBackend::<(), T>::grid(&self.simulation_runner)
+    }
+
+    pub fn dt(&self) -> f32 {
+        SolverBackend::<(), T>::dt(&self.simulation_runner)
+    }
+
+    pub fn steps(&self) -> usize {
+        SolverBackend::<(), T>::steps(&self.simulation_runner)
+    }
+
+    pub fn time(&self) -> f64 {
+        SolverBackend::<(), T>::time(&self.simulation_runner)
+    }
+
+    pub fn initial_norm(&self) -> T {
+        SolverBackend::<(), T>::initial_norm(&self.simulation_runner)
+    }
+
+    // The diagnostics' measurements, if the settings asked for them
+    pub fn monitor(&self) -> Option<&Monitor<T>> {
+        self.monitor.as_ref()
+    }
+
+    // Measures the current psi now, independent of the diagnostics
+    pub fn measure(&mut self) -> Measurement<T> {
+        self.simulation_runner.measure(&mut ())
+    }
+
+    pub fn observables(&mut self) -> Observables {
+        self.simulation_runner.observables(&mut ())
+    }
+
+    // The full state of the run, which `write` saves to a file
+    pub fn checkpoint(&mut self) -> Checkpoint<T> {
+        self.simulation_runner.checkpoint(&mut ())
+    }
+
+    // Continues from a checkpoint of a run with the same settings. A time dependent potential is
+    // only resampled at the next update, `set_potential` can bring it in line before that.
+    pub fn restore(&mut self, checkpoint: &Checkpoint<T>) -> Result<(), Error> {
+        self.simulation_runner.restore(checkpoint, &mut ())?;
+        self.restart_monitor();
+        Ok(())
+    }
+
+    // Writes psi and its density as `.npy` files into `directory` now, see `Snapshots`
+    pub fn write_snapshot(&mut self, directory: &str) -> Result<(), Error> {
+        snapshot::write(directory, self.units, &mut self.simulation_runner, &mut ())
+    }
+
+    // Steps with the potential held fixed, stopping early if the diagnostics abort on a drift or a
+    // recording, checkpoint or snapshot can't be written
+    pub fn step(&mut self, n: usize) -> Result<(), Error> {
+        for _ in 0..n {
+            self.simulation_runner.step(&mut ());
+            self.update_recorder()?;
+            self.update_checkpoint()?;
+            self.update_snapshots()?;
+            self.update_monitor()?;
+        }
+
+        Ok(())
+    }
+
+    // Steps while resampling a time dependent potential every `potential_update_interval` steps
+    pub fn step_with<S: Simulation>(&mut self, simulation: &S, n: usize) -> Result<(), Error> {
+        for _ in 0..n {
+            self.simulation_runner.step(&mut ());
+
+            if let Some(interval) = self.potential_update_interval {
+                if self.steps().is_multiple_of(interval) {
+                    update_potential(&mut self.simulation_runner, simulation, &mut ())?;
+                }
+            }
+
+            self.update_recorder()?;
+            self.update_checkpoint()?;
+            self.update_snapshots()?;
+            self.update_monitor()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn psi(&self) -> &[Complex<T>] {
+        self.simulation_runner.current_wave()
+    }
+
+    pub fn density(&self) -> &[T] {
+        self.simulation_runner.output()
+    }
+
+    // `psi` holds one value per grid point, row by row
+    pub fn set_psi(&mut self, psi: &[Complex<T>]) -> Result<(), Error> {
+        self.simulation_runner.set_psi(psi, &mut ())?;
+        self.restart_monitor();
+        Ok(())
+    }
+
+    pub fn set_potential(&mut self, potential: &[T]) -> Result<(), Error> {
+        self.simulation_runner.set_potential(potential, &mut ())?;
+        self.restart_monitor();
+        Ok(())
+    }
+
+    fn update_monitor(&mut self) -> Result<(), Error> {
+        match &mut self.monitor {
+            Some(monitor) => monitor.update(&mut self.simulation_runner, &mut ()),
+            None => Ok(()),
+        }
+    }
+
+    fn update_recorder(&mut self) -> Result<(), Error> {
+        match &mut self.recorder {
+            Some(recorder) => recorder.update(&mut self.simulation_runner, &mut ()),
+            None => Ok(()),
+        }
+    }
+
+    fn update_checkpoint(&mut self) -> Result<(), Error> {
+        match self.checkpointing {
+            Some(checkpointing) => {
+                checkpoint::update(checkpointing, &mut self.simulation_runner, &mut ())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn update_snapshots(&mut self) -> Result<(), Error> {
+        match self.snapshots {
+            Some(snapshots) => {
+                snapshot::update(snapshots, self.units, &mut self.simulation_runner, &mut ())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn restart_monitor(&mut self) {
+        if let Some(monitor) = &mut self.monitor {
+            monitor.restart(self.simulation_runner.measure(&mut ()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        states::{GaussianPacket, InitialState},
+        testing::TestSimulation,
+    };
+
+    #[test]
+    fn wrong_sizes_are_errors() {
+        let mut solver = Solver::new(&TestSimulation::new()).unwrap();
+        let expected = 64 * 64;
+
+        assert_eq!(
+            solver.set_psi(&[Complex::ZERO; 3]),
+            Err(Error::SizeMismatch {
+                expected,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            solver.set_potential(&[0.0; 3]),
+            Err(Error::SizeMismatch {
+                expected,
+                actual: 3
+            })
+        );
+        assert_eq!(solver.set_psi(&vec![Complex::ZERO; expected]), Ok(()));
+    }
+
+    #[test]
+    fn normalization_rescales_psi_0() {
+        let simulation = TestSimulation {
+            settings: TestSimulation::new().settings.with_normalization(true),
+            psi_0: |x, y| GaussianPacket::new(0.0, 0.0, 6.0, 0.5, 0.0).psi(x, y) * 3.0,
+            ..TestSimulation::new()
+        };
+        let solver = Solver::<f64>::with_precision(&simulation).unwrap();
+
+        // The norm psi_0 had is kept for reporting
+        assert!((solver.grid().norm(solver.psi()) - 1.0).abs() < 1e-12);
+        assert!((solver.initial_norm() - 9.0).abs() < 1e-4);
+    }
+}
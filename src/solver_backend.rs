@@ -0,0 +1,81 @@
+use crate::{Checkpoint, Complex, Error, Grid, Measurement, Observables, Real, Simulation};
+
+pub enum Density<'a, T = f32> {
+    Texture(&'a mut alexandria::Texture),
+    Values(&'a [T]),
+}
+
+// `C` is whatever the backend needs to reach its device, the window for the GPU backend. `T` is the
+// precision of the wave function.
+pub trait SolverBackend<C, T: Real = f32> {
+    fn grid(&self) -> &Grid;
+    fn dt(&self) -> f32;
+    fn steps(&self) -> usize;
+
+    fn time(&self) -> f64 {
+        time(self.steps(), self.dt())
+    }
+
+    // The norm of `psi_0` as the simulation returned it, before any normalization
+    fn initial_norm(&self) -> T;
+
+    fn step(&mut self, context: &mut C);
+
+    fn density(&mut self, context: &mut C) -> Density<'_, T>;
+    fn psi(&mut self, context: &mut C) -> Vec<Complex<T>>;
+    // Both take one value per grid point, row by row
+    fn set_psi(&mut self, psi: &[Complex<T>], context: &mut C) -> Result<(), Error>;
+
+    // The norm and energy of the current psi
+    fn measure(&mut self, context: &mut C) -> Measurement<T>;
+
+    // Positions, momenta and their spreads for the current psi
+    fn observables(&mut self, context: &mut C) -> Observables;
+
+    fn set_potential(&mut self, potential: &[T], context: &mut C) -> Result<(), Error>;
+
+    // The full state needed to continue the run later
+    fn checkpoint(&mut self, context: &mut C) -> Checkpoint<T>;
+
+    // Continues from a checkpoint taken with the same settings. The potential is left as it is.
+    fn restore(&mut self, checkpoint: &Checkpoint<T>, context: &mut C) -> Result<(), Error>;
+}
+
+// The time after `steps` steps. Every time the crate reports comes from here, so recordings,
+// snapshots and checkpoints of the same step agree.
+pub(crate) fn time(steps: usize, dt: f32) -> f64 {
+    steps as f64 * dt as f64
+}
+
+// Samples `psi_0`, rescaling it to unit norm if the settings ask for it, and returns it along with
+// its original norm
+pub(crate) fn sample_psi_0<T: Real, S: Simulation>(
+    grid: &Grid,
+    simulation: &S,
+) -> (Vec<Complex<T>>, T) {
+    let mut psi: Vec<Complex<T>> = grid.sample(|x, y| simulation.psi_0(x, y).cast());
+    let norm = grid.norm(&psi);
+
+    // A zero wave function has nothing to rescale
+    if simulation.simulation_settings().normalize() && norm > T::ZERO {
+        let scale = T::ONE / norm.sqrt();
+        for psi in psi.iter_mut() {
+            *psi *= scale;
+        }
+    }
+
+    (psi, norm)
+}
+
+// Samples the simulation's potential at the backend's current time and uploads it
+pub(crate) fn update_potential<C, T: Real, B: SolverBackend<C, T> + ?Sized, S: Simulation>(
+    backend: &mut B,
+    simulation: &S,
+    context: &mut C,
+) -> Result<(), Error> {
+    let t = backend.time() as f32;
+    let potential = backend
+        .grid()
+        .sample(|x, y| T::from_f32(simulation.time_dependent_potential(x, y, t)));
+    backend.set_potential(&potential, context)
+}
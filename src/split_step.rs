@@ -0,0 +1,206 @@
+use crate::{
+    fft::Fft,
+    settings::{Settings, DIRICHLET, PERIODIC},
+    Complex, Real,
+};
+
+// Split-operator Fourier method, exp(-i * dt * H / h_bar) ~ exp(-i * dt * V / (2 * h_bar))
+// exp(-i * dt * T / h_bar) exp(-i * dt * V / (2 * h_bar)). The kinetic propagator is diagonal in
+// momentum space, where it is exp(DTH_2MI * k^2), and factors into one transform per axis.
+pub(crate) struct SplitStep<T> {
+    num_points_x: usize,
+    num_points_y: usize,
+    dt_hi: Complex<T>, // scaled to turn potentials into hartrees
+
+    axis_x: Axis<T>,
+    axis_y: Axis<T>,
+
+    line: Vec<Complex<T>>,
+}
+
+// Transforms along one axis. A periodic axis is transformed as is, otherwise each line is extended
+// to a periodic one with the same boundary behaviour: an odd extension about the walls just outside
+// the grid for Dirichlet, an even extension about the edge points for Neumann.
+struct Axis<T> {
+    boundary: u32,
+    fft: Fft<T>,
+    kinetic: Vec<Complex<T>>,
+    extended: Vec<Complex<T>>,
+}
+
+impl<T: Real> SplitStep<T> {
+    pub fn new(settings: &Settings<T>) -> Self {
+        let num_points_x = settings.num_points_x as usize;
+        let num_points_y = settings.num_points_y as usize;
+
+        SplitStep {
+            num_points_x,
+            num_points_y,
+            dt_hi: settings.dt_hi * settings.potential_scale,
+            axis_x: Axis::new(
+                settings.dth_2mi,
+                settings.dx2,
+                num_points_x,
+                settings.boundary_x,
+            ),
+            axis_y: Axis::new(
+                settings.dth_2mi,
+                settings.dy2,
+                num_points_y,
+                settings.boundary_y,
+            ),
+            line: vec![Complex::ZERO; num_points_y],
+        }
+    }
+
+    pub fn step(
+        &mut self,
+        current_wave: &[Complex<T>],
+        next_wave: &mut [Complex<T>],
+        potential: &[T],
+    ) {
+        let num_points_x = self.num_points_x;
+        let num_points_y = self.num_points_y;
+
+        for ((next, current), potential) in next_wave.iter_mut().zip(current_wave).zip(potential) {
+            *next = *current * self.potential_propagator(*potential);
+        }
+
+        for row in next_wave.chunks_exact_mut(num_points_x) {
+            self.axis_x.propagate(row);
+        }
+
+        for x in 0..num_points_x {
+            for y in 0..num_points_y {
+                self.line[y] = next_wave[x + y * num_points_x];
+            }
+
+            self.axis_y.propagate(&mut self.line);
+
+            for y in 0..num_points_y {
+                next_wave[x + y * num_points_x] = self.line[y];
+            }
+        }
+
+        for (next, potential) in next_wave.iter_mut().zip(potential) {
+            *next *= self.potential_propagator(*potential);
+        }
+    }
+
+    // exp(DT_HI * V / 2)
+    fn potential_propagator(&self, potential: T) -> Complex<T> {
+        (self.dt_hi * (T::from_f32(0.5) * potential)).exp()
+    }
+}
+
+impl<T: Real> Axis<T> {
+    pub fn new(dth_2mi: Complex<T>, d2: T, n: usize, boundary: u32) -> Self {
+        let length = match boundary {
+            PERIODIC => n,
+            DIRICHLET => 2 * (n + 1),
+            _ => 2 * n,
+        };
+
+        Axis {
+            boundary,
+            fft: Fft::new(length),
+            kinetic: kinetic_propagator(dth_2mi, d2, length),
+            extended: vec![Complex::ZERO; length],
+        }
+    }
+
+    // Applies the kinetic propagator to a line of the grid in place
+    pub fn propagate(&mut self, line: &mut [Complex<T>]) {
+        let n = line.len();
+
+        match self.boundary {
+            PERIODIC => self.extended.copy_from_slice(line),
+            DIRICHLET => {
+                // [0, psi_0 .. psi_{n-1}, 0, -psi_{n-1} .. -psi_0]
+                self.extended[0] = Complex::ZERO;
+                self.extended[n + 1] = Complex::ZERO;
+                for (i, psi) in line.iter().enumerate() {
+                    self.extended[i + 1] = *psi;
+                    self.extended[2 * n + 1 - i] = -*psi;
+                }
+            }
+            _ => {
+                // [psi_0 .. psi_{n-1}, psi_{n-1} .. psi_0]
+                for (i, psi) in line.iter().enumerate() {
+                    self.extended[i] = *psi;
+                    self.extended[2 * n - 1 - i] = *psi;
+                }
+            }
+        }
+
+        self.fft.forward(&mut self.extended);
+        for (psi, kinetic) in self.extended.iter_mut().zip(&self.kinetic) {
+            *psi *= *kinetic;
+        }
+        self.fft.inverse(&mut self.extended);
+
+        let offset = if self.boundary == DIRICHLET { 1 } else { 0 };
+        line.copy_from_slice(&self.extended[offset..offset + n]);
+    }
+}
+
+// exp(DTH_2MI * k^2) for each wavenumber in FFT order
+fn kinetic_propagator<T: Real>(dth_2mi: Complex<T>, d2: T, n: usize) -> Vec<Complex<T>> {
+    (0..n)
+        .map(|j| {
+            let j = if j < n.div_ceil(2) {
+                T::from_usize(j)
+            } else {
+                T::from_usize(j) - T::from_usize(n)
+            };
+            let k2 = (T::from_f32(2.0) * T::PI * j / T::from_usize(n)).powi(2) / d2;
+
+            (dth_2mi * k2).exp()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        states::{GaussianPacket, InitialState},
+        testing::{self, TestSimulation},
+        Integrator, Solver,
+    };
+
+    fn simulation(integrator: Integrator) -> TestSimulation {
+        TestSimulation {
+            psi_0: |x, y| GaussianPacket::new(0.0, 0.0, 6.0, 0.25, 0.0).psi(x, y),
+            ..TestSimulation::with_integrator(integrator)
+        }
+    }
+
+    #[test]
+    fn keeps_the_norm() {
+        let mut solver = Solver::<f64>::with_precision(&simulation(Integrator::SplitStep)).unwrap();
+        let initial = solver.grid().norm(solver.psi());
+
+        solver.step(200).unwrap();
+        let drift = testing::norm_drift(solver.grid(), solver.psi(), initial);
+        assert!(drift < 1e-9, "norm drifted by {:e}", drift);
+    }
+
+    #[test]
+    fn agrees_with_crank_nicolson_on_a_free_packet() {
+        let mut split_step =
+            Solver::<f64>::with_precision(&simulation(Integrator::SplitStep)).unwrap();
+        let mut crank_nicolson =
+            Solver::<f64>::with_precision(&simulation(Integrator::CrankNicolson)).unwrap();
+        split_step.step(200).unwrap();
+        crank_nicolson.step(200).unwrap();
+
+        let difference: Vec<_> = split_step
+            .psi()
+            .iter()
+            .zip(crank_nicolson.psi())
+            .map(|(a, b)| *a - *b)
+            .collect();
+        let distance = (split_step.grid().norm(&difference) / split_step.initial_norm()).sqrt();
+        assert!(distance < 0.01, "solutions differ by {:e}", distance);
+    }
+}
@@ -0,0 +1,253 @@
+// Ready made initial states. Each one can be returned from `Simulation::psi_0` as
+// `self.state.psi(x, y)`, or combined with others in a `Superposition`.
+
+use crate::{Boundary, Complex, SimulationSettings, UnitSystem};
+use std::f32::consts::PI;
+
+pub trait InitialState {
+    fn psi(&self, x: f32, y: f32) -> Complex;
+}
+
+// Any `Fn(x, y) -> Complex` is an initial state, so hand written states mix with the ones here
+impl<F: Fn(f32, f32) -> Complex> InitialState for F {
+    fn psi(&self, x: f32, y: f32) -> Complex {
+        self(x, y)
+    }
+}
+
+// A normalized Gaussian exp(-|r - r_0|^2 / (4 * sigma^2)) moving with wavevector (kx, ky), so
+// sigma is the standard deviation of the position density and the momentum is h_bar * k
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GaussianPacket {
+    x0: f32,
+    y0: f32,
+    sigma: f32,
+    kx: f32,
+    ky: f32,
+}
+
+// exp(i * (kx * x + ky * y)) with unit amplitude
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PlaneWave {
+    kx: f32,
+    ky: f32,
+}
+
+// The normalized (nx, ny) eigenstate of an isotropic harmonic oscillator centred on (x0, y0), a
+// product of Hermite-Gauss functions with the oscillator length sqrt(h_bar / (m * omega))
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HermiteGauss {
+    nx: usize,
+    ny: usize,
+    length: f32,
+    x0: f32,
+    y0: f32,
+}
+
+// The normalized (mx, my) eigenmode of a box with hard walls, centred on the origin. Modes count
+// from 1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BoxMode {
+    mx: usize,
+    my: usize,
+    width: f32,
+    height: f32,
+}
+
+// sum_i c_i * psi_i(x, y)
+#[derive(Default)]
+pub struct Superposition {
+    terms: Vec<(Complex, Box<dyn InitialState>)>,
+}
+
+impl GaussianPacket {
+    pub fn new(x0: f32, y0: f32, sigma: f32, kx: f32, ky: f32) -> Self {
+        GaussianPacket {
+            x0,
+            y0,
+            sigma,
+            kx,
+            ky,
+        }
+    }
+}
+
+impl InitialState for GaussianPacket {
+    fn psi(&self, x: f32, y: f32) -> Complex {
+        let dx = x - self.x0;
+        let dy = y - self.y0;
+        let envelope = (-(dx * dx + dy * dy) / (4.0 * self.sigma * self.sigma)).exp()
+            / ((2.0 * PI).sqrt() * self.sigma);
+
+        Complex::plane_wave(self.kx, self.ky, dx, dy) * envelope
+    }
+}
+
+impl PlaneWave {
+    pub fn new(kx: f32, ky: f32) -> Self {
+        PlaneWave { kx, ky }
+    }
+}
+
+impl InitialState for PlaneWave {
+    fn psi(&self, x: f32, y: f32) -> Complex {
+        Complex::plane_wave(self.kx, self.ky, x, y)
+    }
+}
+
+impl HermiteGauss {
+    pub fn new(nx: usize, ny: usize, length: f32) -> Self {
+        HermiteGauss {
+            nx,
+            ny,
+            length,
+            x0: 0.0,
+            y0: 0.0,
+        }
+    }
+
+    // The eigenstate of V = m * omega^2 * r^2 / 2, with `mass` and `omega` in `units`
+    pub fn oscillator(nx: usize, ny: usize, mass: f32, omega: f32, units: UnitSystem) -> Self {
+        let length = (units.h_bar() / (mass as f64 * omega as f64)).sqrt();
+        HermiteGauss::new(nx, ny, length as f32)
+    }
+
+    pub fn with_center(self, x0: f32, y0: f32) -> Self {
+        HermiteGauss { x0, y0, ..self }
+    }
+}
+
+impl InitialState for HermiteGauss {
+    fn psi(&self, x: f32, y: f32) -> Complex {
+        let psi_x = hermite_function(self.nx, ((x - self.x0) / self.length) as f64);
+        let psi_y = hermite_function(self.ny, ((y - self.y0) / self.length) as f64);
+
+        Complex::from((psi_x * psi_y) as f32 / self.length)
+    }
+}
+
+impl BoxMode {
+    pub fn new(mx: usize, my: usize, width: f32, height: f32) -> Self {
+        BoxMode {
+            mx,
+            my,
+            width,
+            height,
+        }
+    }
+
+    // The mode of the box formed by the grid's Dirichlet walls, one grid spacing past each edge.
+    // Only meaningful along Dirichlet axes: along a Neumann or periodic axis the sine spans the
+    // grid's own extent, which is not an eigenmode of that boundary and won't stay stationary.
+    pub fn for_grid(mx: usize, my: usize, settings: &SimulationSettings) -> Self {
+        let extent = |num_points: usize, d: f32, boundary: Boundary| match boundary {
+            Boundary::Dirichlet => (num_points + 1) as f32 * d,
+            _ => num_points as f32 * d,
+        };
+
+        BoxMode::new(
+            mx,
+            my,
+            extent(
+                settings.num_points_x(),
+                settings.dx(),
+                settings.boundary_x(),
+            ),
+            extent(
+                settings.num_points_y(),
+                settings.dy(),
+                settings.boundary_y(),
+            ),
+        )
+    }
+}
+
+impl InitialState for BoxMode {
+    fn psi(&self, x: f32, y: f32) -> Complex {
+        let psi_x = (self.mx as f32 * PI * (x / self.width + 0.5)).sin();
+        let psi_y = (self.my as f32 * PI * (y / self.height + 0.5)).sin();
+
+        Complex::from(2.0 * psi_x * psi_y / (self.width * self.height).sqrt())
+    }
+}
+
+impl Superposition {
+    pub fn new() -> Self {
+        Superposition { terms: Vec::new() }
+    }
+
+    pub fn with<S: InitialState + 'static>(mut self, coefficient: Complex, state: S) -> Self {
+        self.terms.push((coefficient, Box::new(state)));
+        self
+    }
+}
+
+impl InitialState for Superposition {
+    fn psi(&self, x: f32, y: f32) -> Complex {
+        self.terms
+            .iter()
+            .map(|(coefficient, state)| *coefficient * state.psi(x, y))
+            .sum()
+    }
+}
+
+// The normalized 1D oscillator eigenfunction of order n in units of the oscillator length, built
+// with the recurrence for normalized functions so high orders don't overflow
+fn hermite_function(n: usize, xi: f64) -> f64 {
+    let mut previous = 0.0;
+    let mut current = std::f64::consts::PI.powf(-0.25) * (-xi * xi / 2.0).exp();
+    for k in 0..n {
+        let next = (2.0 / (k + 1) as f64).sqrt() * xi * current
+            - (k as f64 / (k + 1) as f64).sqrt() * previous;
+        previous = current;
+        current = next;
+    }
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid;
+
+    fn norm<S: InitialState>(state: S, settings: &SimulationSettings) -> f32 {
+        let grid = Grid::new(settings).unwrap();
+        grid.norm(&grid.sample(|x, y| state.psi(x, y)))
+    }
+
+    #[test]
+    fn states_are_normalized() {
+        let settings = SimulationSettings::new(80, 80, 0.25, 0.25, 0.01, 1.0)
+            .with_units(UnitSystem::Dimensionless);
+
+        for (name, norm) in [
+            (
+                "Gaussian packet",
+                norm(GaussianPacket::new(0.5, -1.0, 2.0, 3.0, -1.0), &settings),
+            ),
+            (
+                "Hermite-Gauss",
+                norm(
+                    HermiteGauss::new(3, 1, 1.5).with_center(-0.5, 1.0),
+                    &settings,
+                ),
+            ),
+            (
+                "oscillator state",
+                norm(
+                    HermiteGauss::oscillator(2, 2, 1.0, 0.5, UnitSystem::Dimensionless),
+                    &settings,
+                ),
+            ),
+        ] {
+            assert!((norm - 1.0).abs() < 1e-4, "{} has norm {}", name, norm);
+        }
+
+        // The sines sum to exactly half the box on the grid inside its walls
+        let settings = settings.with_boundaries(Boundary::Dirichlet, Boundary::Dirichlet);
+        for (mx, my) in [(1, 1), (2, 3), (5, 1)] {
+            let norm = norm(BoxMode::for_grid(mx, my, &settings), &settings);
+            assert!((norm - 1.0).abs() < 1e-4, "box mode has norm {}", norm);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    states::{GaussianPacket, InitialState},
+    Complex, Grid, Integrator, RenderSettings, Simulation, SimulationSettings, UnitSystem,
+};
+
+// A simulation assembled from plain functions, by default a Gaussian packet at rest on a free
+// 64 x 64 grid in dimensionless units. The potential is only given through
+// `time_dependent_potential`, which is what every solver samples.
+pub(crate) struct TestSimulation {
+    pub settings: SimulationSettings,
+    pub psi_0: fn(f32, f32) -> Complex,
+    pub potential: fn(f32, f32, f32) -> f32,
+}
+
+impl TestSimulation {
+    pub fn with_integrator(integrator: Integrator) -> Self {
+        let simulation = TestSimulation::new();
+        TestSimulation {
+            settings: simulation.settings.with_integrator(integrator),
+            ..simulation
+        }
+    }
+
+    // A Gaussian guess in V = r^2 / 2, whose levels are E = n + 1 for n = nx + ny
+    pub fn trap() -> Self {
+        TestSimulation {
+            settings: SimulationSettings::new(40, 40, 0.3, 0.3, 0.02, 1.0)
+                .with_units(UnitSystem::Dimensionless),
+            psi_0: |x, y| GaussianPacket::new(0.5, 0.25, 1.5, 0.0, 0.0).psi(x, y),
+            potential: |x, y, _| 0.5 * (x * x + y * y),
+        }
+    }
+}
+
+// How far the norm of `psi` is from `initial`, relative to it
+pub(crate) fn norm_drift(grid: &Grid, psi: &[Complex<f64>], initial: f64) -> f64 {
+    (grid.norm(psi) / initial - 1.0).abs()
+}
+
+impl Simulation for TestSimulation {
+    fn new() -> Self {
+        TestSimulation {
+            settings: SimulationSettings::new(64, 64, 1.0, 1.0, 0.05, 1.0)
+                .with_units(UnitSystem::Dimensionless),
+            psi_0: |x, y| GaussianPacket::new(0.0, 0.0, 6.0, 0.0, 0.0).psi(x, y),
+            potential: |_, _, _| 0.0,
+        }
+    }
+
+    fn simulation_settings(&self) -> SimulationSettings {
+        self.settings
+    }
+
+    fn render_settings(&self) -> RenderSettings {
+        RenderSettings::new(8, 8, 1.0, 1.0)
+    }
+
+    fn time_scale(&self) -> f32 {
+        1.0
+    }
+
+    fn psi_0(&self, x: f32, y: f32) -> Complex {
+        (self.psi_0)(x, y)
+    }
+
+    fn time_dependent_potential(&self, x: f32, y: f32, t: f32) -> f32 {
+        (self.potential)(x, y, t)
+    }
+}